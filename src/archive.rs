@@ -0,0 +1,57 @@
+use std::io::{Cursor, Write};
+
+use futures::{future::BoxFuture, stream::FuturesOrdered, TryStreamExt};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::error::{PDF2ImageError, Result};
+use crate::pdf::{render_page_bytes, Pages, PdfInfo};
+use crate::render_options::RenderOptions;
+
+/// Renders the requested pages and packs them into a ZIP archive,
+/// one image per page named `page-N.<ext>`
+///
+/// Page numbers are zero-padded to the width of the page count so the
+/// entries sort in page order. Each page is written to the archive as soon
+/// as it is rendered and its buffer dropped. JPEG, PNG and WebP images are
+/// stored as they are already compressed, uncompressed PNM images are deflated
+pub async fn render_pdf_to_zip(
+    data: &[u8],
+    info: &PdfInfo,
+    pages: Pages,
+    options: &RenderOptions,
+) -> Result<Vec<u8>> {
+    if info.is_encrypted() && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
+
+    let pages_range = pages.resolve_with_options(info, options)?;
+
+    let width = info.page_count().to_string().len();
+    let extension = options.file_extension();
+    let compression = match options.output_format() {
+        image::ImageFormat::Pnm => CompressionMethod::Deflated,
+        _ => CompressionMethod::Stored,
+    };
+    let file_options = SimpleFileOptions::default().compression_method(compression);
+
+    let mut renders = pages_range
+        .into_iter()
+        .map(|page| -> BoxFuture<'_, Result<(u32, Vec<u8>)>> {
+            Box::pin(async move {
+                render_page_bytes(data, info, page, options)
+                    .await
+                    .map(|image| (page, image))
+                    .map_err(|err| err.with_page(page))
+            })
+        })
+        .collect::<FuturesOrdered<BoxFuture<'_, Result<(u32, Vec<u8>)>>>>();
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    while let Some((page, image)) = renders.try_next().await? {
+        writer.start_file(format!("page-{page:0width$}.{extension}"), file_options)?;
+        writer.write_all(&image)?;
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
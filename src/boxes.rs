@@ -0,0 +1,135 @@
+use tokio::process::Command;
+
+use crate::error::{PDF2ImageError, Result};
+use crate::pdf::{get_executable_path, run_poppler};
+use crate::render_options::RenderOptions;
+
+/// A rectangle in PDF points given by its lower left and upper right corners
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdfRect {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+}
+
+impl PdfRect {
+    /// The width of the rectangle in points
+    pub fn width(&self) -> f64 {
+        self.x2 - self.x1
+    }
+
+    /// The height of the rectangle in points
+    pub fn height(&self) -> f64 {
+        self.y2 - self.y1
+    }
+}
+
+/// The page boundary boxes of a single page in PDF points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageBoxes {
+    /// The full page area
+    pub media: PdfRect,
+    /// The visible page area, poppler renders relative to this
+    pub crop: PdfRect,
+    pub bleed: Option<PdfRect>,
+    pub trim: Option<PdfRect>,
+    pub art: Option<PdfRect>,
+}
+
+/// Reads the page boundary boxes of a page using pdfinfo -box
+///
+/// Useful for mapping rendered pixel coordinates back to PDF coordinates,
+/// pdfinfo reports the bleed, trim and art boxes as the crop box when
+/// the page doesn't set them. The executable overrides, environment and
+/// password of the `options` apply
+pub async fn page_boxes(data: &[u8], page: u32, options: &RenderOptions) -> Result<PageBoxes> {
+    let mut command = Command::new(get_executable_path("pdfinfo", options));
+    command
+        .env("LC_ALL", "C")
+        .arg("-box")
+        .args(["-f", &page.to_string(), "-l", &page.to_string()]);
+    if let Some(password) = &options.password {
+        command.args(password.cli_args());
+    }
+    command.arg("-");
+
+    let output = run_poppler(command, data, options).await?;
+
+    parse_page_boxes(&String::from_utf8_lossy(&output))
+}
+
+/// Parses the boxes from the pdfinfo -box output of a single page
+fn parse_page_boxes(output: &str) -> Result<PageBoxes> {
+    // Boxes are reported as "Page    1 MediaBox:     0.00     0.00   612.00   792.00"
+    let find_box = |name: &str| {
+        output.lines().find_map(|line| {
+            let (_, values) = line.split_once(name)?;
+            let mut values = values.split_whitespace().map(str::parse::<f64>);
+            Some(PdfRect {
+                x1: values.next()?.ok()?,
+                y1: values.next()?.ok()?,
+                x2: values.next()?.ok()?,
+                y2: values.next()?.ok()?,
+            })
+        })
+    };
+
+    Ok(PageBoxes {
+        media: find_box("MediaBox:").ok_or(PDF2ImageError::UnableToExtractPageBoxes)?,
+        crop: find_box("CropBox:").ok_or(PDF2ImageError::UnableToExtractPageBoxes)?,
+        bleed: find_box("BleedBox:"),
+        trim: find_box("TrimBox:"),
+        art: find_box("ArtBox:"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_distinct_media_and_crop_boxes() {
+        let output = "\
+Pages:          1
+Page    1 size: 500 x 700 pts
+Page    1 rot:  0
+Page    1 MediaBox:     0.00     0.00   612.00   792.00
+Page    1 CropBox:     56.00    46.00   556.00   746.00
+Page    1 BleedBox:    56.00    46.00   556.00   746.00
+Page    1 TrimBox:     56.00    46.00   556.00   746.00
+Page    1 ArtBox:      56.00    46.00   556.00   746.00
+";
+
+        let boxes = parse_page_boxes(output).unwrap();
+
+        assert_eq!(
+            boxes.media,
+            PdfRect {
+                x1: 0.0,
+                y1: 0.0,
+                x2: 612.0,
+                y2: 792.0,
+            }
+        );
+        assert_eq!(
+            boxes.crop,
+            PdfRect {
+                x1: 56.0,
+                y1: 46.0,
+                x2: 556.0,
+                y2: 746.0,
+            }
+        );
+        assert_eq!((boxes.crop.width(), boxes.crop.height()), (500.0, 700.0));
+        assert_eq!(boxes.trim, Some(boxes.crop));
+    }
+
+    #[test]
+    fn parse_missing_boxes_fails() {
+        assert!(matches!(
+            parse_page_boxes("Pages: 1\n"),
+            Err(PDF2ImageError::UnableToExtractPageBoxes)
+        ));
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use lru::LruCache;
+
+use crate::error::{PDF2ImageError, Result};
+use crate::pdf::{check_render_limits, render_pdf_single_page, PdfInfo};
+use crate::render_options::RenderOptions;
+
+/// In-process LRU cache of rendered pages keyed by the pdf content
+/// hash, page number and the options that affect the output
+pub struct PageCache {
+    inner: Mutex<LruCache<CacheKey, image::DynamicImage>>,
+}
+
+#[derive(Hash, PartialEq, Eq)]
+struct CacheKey {
+    /// blake3 hash of the pdf data
+    data_hash: [u8; 32],
+    /// The rendered page number
+    page: u32,
+    /// blake3 hash of the password, so a cached page of an encrypted pdf is
+    /// only returned for the password it was rendered with
+    password_hash: Option<[u8; 32]>,
+    /// The options that affect the rendered output
+    options: String,
+}
+
+impl CacheKey {
+    fn new(data: &[u8], page: u32, options: &RenderOptions) -> Self {
+        let password_hash = options.password.as_ref().map(|password| {
+            // The flag tells a user password apart from the same owner password
+            let [flag, value] = password.cli_args();
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(flag.as_bytes());
+            hasher.update(&[0]);
+            hasher.update(value.as_bytes());
+            *hasher.finalize().as_bytes()
+        });
+
+        // Only options that change the output are included, the password
+        // is only included as a hash so it is never retained by the cache
+        let options = format!(
+            "{:?}|{:?}|{:?}|{}|{:?}|{}|{}|{}|{:?}|{:?}|{:?}|{:?}|{}|{:?}|{}|{}|{:?}|{:?}",
+            options.resolution,
+            options.scale,
+            options.scale_to,
+            options.greyscale,
+            options.crop,
+            options.crop_from_media_box,
+            options.pdftocairo,
+            options.fallback_to_pdftoppm,
+            options.output_format(),
+            // Sorted so the key doesn't depend on the map iteration order
+            options.binary_overrides.iter().collect::<BTreeMap<_, _>>(),
+            options.resize,
+            options.flatten_background,
+            options.render_annotations,
+            options.env,
+            options.mono,
+            options.respect_user_unit,
+            options.auto_downscale_to,
+            options.exact_size,
+        );
+
+        Self {
+            data_hash: *blake3::hash(data).as_bytes(),
+            page,
+            password_hash,
+            options,
+        }
+    }
+}
+
+impl PageCache {
+    /// Creates a new cache holding at most `capacity` rendered pages
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            inner: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Removes all the cached pages
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruCache<CacheKey, image::DynamicImage>> {
+        // The cache contents are still valid if another user panicked
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Renders a single page of the PDF using the `cache` to skip
+/// rendering pages that have already been rendered with the same options
+pub async fn render_pdf_single_page_cached(
+    cache: &PageCache,
+    data: &[u8],
+    info: &PdfInfo,
+    page: u32,
+    options: &RenderOptions,
+) -> Result<image::DynamicImage> {
+    // Checked before the lookup so an encrypted pdf without a password
+    // can't hit a page cached with one
+    if info.is_encrypted() && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
+
+    // A cached page must still be rejected by a tighter max_pixels limit
+    check_render_limits(info, page, options)?;
+
+    let key = CacheKey::new(data, page, options);
+
+    if let Some(image) = cache.lock().get(&key) {
+        return Ok(image.clone());
+    }
+
+    let image = render_pdf_single_page(data, info, page, options).await?;
+    cache.lock().put(key, image.clone());
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(options: &RenderOptions) -> CacheKey {
+        CacheKey::new(b"%PDF-1.7", 1, options)
+    }
+
+    #[test]
+    fn key_includes_renderer_selection() {
+        let default = RenderOptions::default();
+        let fallback = RenderOptions {
+            fallback_to_pdftoppm: true,
+            ..RenderOptions::default()
+        };
+        let overridden = RenderOptions {
+            binary_overrides: [("pdftoppm", "/opt/poppler/pdftoppm".to_string())].into(),
+            ..RenderOptions::default()
+        };
+
+        assert!(key(&default) != key(&fallback));
+        assert!(key(&default) != key(&overridden));
+    }
+
+    #[test]
+    fn key_ignores_override_order() {
+        let overrides = [
+            ("pdftoppm", "/opt/poppler/pdftoppm".to_string()),
+            ("pdftocairo", "/opt/poppler/pdftocairo".to_string()),
+            ("pdfinfo", "/opt/poppler/pdfinfo".to_string()),
+        ];
+        let forward = RenderOptions {
+            binary_overrides: overrides.clone().into_iter().collect(),
+            ..RenderOptions::default()
+        };
+        let reverse = RenderOptions {
+            binary_overrides: overrides.into_iter().rev().collect(),
+            ..RenderOptions::default()
+        };
+
+        assert!(key(&forward) == key(&reverse));
+    }
+
+    #[test]
+    fn key_hashes_password() {
+        let password = |password: crate::Password| RenderOptions {
+            password: Some(password),
+            ..RenderOptions::default()
+        };
+        let correct = key(&password(crate::Password::User("secret".to_string())));
+        let wrong = key(&password(crate::Password::User("guess".to_string())));
+        let owner = key(&password(crate::Password::Owner("secret".to_string())));
+
+        assert!(!correct.options.contains("secret"));
+        assert!(correct != wrong);
+        assert!(correct != owner);
+        assert!(correct == key(&password(crate::Password::User("secret".to_string()))));
+    }
+
+    #[tokio::test]
+    async fn second_call_hits_the_cache() {
+        let data = b"%PDF-1.7";
+        let info = PdfInfo::with_page_count(1);
+        // Rendering would fail as the renderer doesn't exist
+        let options = RenderOptions {
+            binary_overrides: [("pdftoppm", "/nonexistent/pdftoppm".to_string())].into(),
+            ..RenderOptions::default()
+        };
+        let cached = image::DynamicImage::ImageRgb8(image::RgbImage::new(3, 2));
+
+        let cache = PageCache::new(NonZeroUsize::new(4).unwrap());
+        cache
+            .lock()
+            .put(CacheKey::new(data, 1, &options), cached.clone());
+
+        let image = render_pdf_single_page_cached(&cache, data, &info, 1, &options)
+            .await
+            .unwrap();
+        assert_eq!(image, cached);
+
+        // A different page isn't cached and fails to spawn the renderer
+        assert!(render_pdf_single_page_cached(
+            &cache,
+            data,
+            &PdfInfo::with_page_count(2),
+            2,
+            &options
+        )
+        .await
+        .is_err());
+    }
+}
@@ -0,0 +1,26 @@
+use std::sync::{Arc, OnceLock};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// The process wide limit on running poppler processes, when installed
+static GLOBAL_LIMIT: OnceLock<Arc<Semaphore>> = OnceLock::new();
+
+/// Limits the number of poppler processes running at once across every
+/// call in the process, returning false when a limit was already installed
+///
+/// The limit can only be installed once and applies to every function that
+/// spawns poppler, each process waits for a free slot before starting
+pub fn set_global_concurrency(limit: usize) -> bool {
+    GLOBAL_LIMIT
+        .set(Arc::new(Semaphore::new(limit.max(1))))
+        .is_ok()
+}
+
+/// Waits for a free process slot when a global limit is installed, the
+/// slot is released when the permit is dropped
+pub(crate) async fn acquire_process_slot() -> Option<OwnedSemaphorePermit> {
+    let semaphore = GLOBAL_LIMIT.get()?.clone();
+
+    // UNWRAP SAFETY: The semaphore is never closed
+    Some(semaphore.acquire_owned().await.unwrap())
+}
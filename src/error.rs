@@ -12,20 +12,92 @@ pub enum PDF2ImageError {
     /// A UTF-8 parsing error.
     #[error("utf-8 parsing error: {0}")]
     Utf8(#[from] std::str::Utf8Error),
+    /// An invalid UTF-8 text extraction error when using [TextEncoding::Strict](crate::TextEncoding::Strict).
+    #[error("extracted text is not valid utf-8: {0}")]
+    InvalidUtf8(std::string::FromUtf8Error),
     /// An integer parsing error.
     #[error("int parsing error: {0}")]
     ParseInt(#[from] std::num::ParseIntError),
     /// An image error.
     #[error("image error: {0}")]
     ImageError(#[from] image::ImageError),
+    /// An error writing a ZIP archive.
+    #[cfg(feature = "zip")]
+    #[error("zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
     /// An error indicating that the builder is misconfigured.
-    #[error("RenderOptionsBuilder error: {0}")]
-    RenderOptionsBuilder(#[from] crate::render_options::RenderOptionsBuilderError),
+    #[error("invalid {field} option: {reason}")]
+    InvalidOptions { field: &'static str, reason: String },
+    /// An error from rendering or extracting a specific page of a multi-page operation.
+    #[error("page {page}: {source}")]
+    PageRender {
+        page: u32,
+        source: Box<PDF2ImageError>,
+    },
+    /// An error indicating that the installed poppler is too old for an option.
+    #[error("{option} requires poppler {required} or newer, found {found}")]
+    PopplerTooOld {
+        option: &'static str,
+        required: crate::version::PopplerVersion,
+        found: crate::version::PopplerVersion,
+    },
+    /// An error indicating that a requested page doesn't exist in the PDF.
+    #[error("page {page} is out of range, the PDF has {page_count} pages")]
+    PageOutOfRange { page: u32, page_count: u32 },
+    /// An error indicating that a poppler command wrote more than the output limit.
+    #[error("output exceeded the limit of {limit} bytes")]
+    OutputLimitExceeded { limit: u64 },
+    /// An error indicating that a poppler command exited unsuccessfully.
+    #[error("{executable} exited with {status}: {stderr}")]
+    CommandFailed {
+        executable: &'static str,
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
+    /// An error indicating that the rendered page would exceed the pixel limit.
+    #[error("rendered page would be {pixels} pixels exceeding the limit of {limit}")]
+    OutputTooLarge { pixels: u64, limit: u64 },
     /// An error indicating that the PDF is encrypted and no password was provided.
     #[error("No password given for encrypted PDF")]
     NoPasswordForEncryptedPDF,
+    /// An error indicating that an option can't be used with the backend.
+    #[error("{option} is not supported by {}", .backend.executable())]
+    UnsupportedOption {
+        backend: crate::render_options::Backend,
+        option: &'static str,
+    },
+    /// An error indicating that neither pdftocairo or pdftoppm could be found.
+    #[error("no renderer available, pdftocairo and pdftoppm were not found")]
+    NoRendererAvailable,
+    /// An error indicating that the output format is only supported by pdftocairo.
+    #[error("output format requires the pdftocairo backend")]
+    PdftocairoRequired,
+    /// An error indicating that the selected pages do not form a contiguous range.
+    #[error("selected pages must form a contiguous range")]
+    NonContiguousPages,
+    /// An error indicating that no PDFs were given to merge.
+    #[error("no PDFs to merge")]
+    NoPdfsToMerge,
     #[error("unable to extract page count")]
     UnableToExtractPageCount,
     #[error("unable to extract encryption status")]
     UnableToExtractEncryptionStatus,
+    #[error("unable to extract linearization status")]
+    UnableToExtractLinearizationStatus,
+    #[error("unable to parse font list")]
+    UnableToParseFontList,
+    #[error("unable to extract poppler version")]
+    UnableToExtractPopplerVersion,
+    #[error("unable to extract page boxes")]
+    UnableToExtractPageBoxes,
+}
+
+impl PDF2ImageError {
+    /// Wraps the error with the page number it originated from
+    pub(crate) fn with_page(self, page: u32) -> Self {
+        PDF2ImageError::PageRender {
+            page,
+            source: Box::new(self),
+        }
+    }
 }
@@ -0,0 +1,154 @@
+use tokio::process::Command;
+
+use crate::error::{PDF2ImageError, Result};
+use crate::pdf::{get_executable_path, run_poppler_checked};
+use crate::render_options::RenderOptions;
+
+/// Details about a font used within a PDF
+#[derive(Debug, Clone)]
+pub struct FontInfo {
+    /// The font name, including the subset tag if present
+    pub name: String,
+    /// The font type (e.g. "Type 1", "TrueType", "CID Type 0C")
+    pub type_: String,
+    /// Whether the font is embedded in the PDF
+    pub embedded: bool,
+    /// Whether the font is a subset
+    pub subset: bool,
+    /// The font encoding (e.g. "WinAnsi", "Identity-H")
+    pub encoding: String,
+}
+
+/// Lists the fonts used within the pdf file using pdffonts, the
+/// executable overrides, environment and password of the `options` apply
+pub async fn list_fonts(data: &[u8], options: &RenderOptions) -> Result<Vec<FontInfo>> {
+    let mut command = Command::new(get_executable_path("pdffonts", options));
+    if let Some(password) = &options.password {
+        command.args(password.cli_args());
+    }
+    command.arg("-");
+
+    let output = run_poppler_checked(command, data, options, "pdffonts").await?;
+    // Font names are raw PDF strings that aren't necessarily UTF-8
+    let output = String::from_utf8_lossy(&output);
+
+    let mut lines = output.lines();
+
+    // Skip the header row, the separator row beneath it gives the column widths
+    lines.next();
+    let name_width = match lines.next() {
+        Some(separator) => separator
+            .split(' ')
+            .next()
+            .map(str::len)
+            .ok_or(PDF2ImageError::UnableToParseFontList)?,
+        // No fonts in the document
+        None => return Ok(Vec::new()),
+    };
+
+    lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse_font_line(line, name_width))
+        .collect()
+}
+
+/// Parses a single row of the pdffonts table
+///
+/// The name and type columns can both contain spaces so the fixed
+/// columns are taken from the end of the row, the name column width
+/// from the separator row is used to split the remaining name and type
+fn parse_font_line(line: &str, name_width: usize) -> Result<FontInfo> {
+    let columns: Vec<&str> = line.split_whitespace().collect();
+
+    // Trailing columns: encoding, emb, sub, uni, object, generation
+    if columns.len() < 8 {
+        return Err(PDF2ImageError::UnableToParseFontList);
+    }
+
+    let (head, tail) = columns.split_at(columns.len() - 6);
+
+    let parse_flag = |value: &str| match value {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(PDF2ImageError::UnableToParseFontList),
+    };
+
+    // Names longer than the column push the other columns along, in that
+    // case the name is assumed to be the first whitespace separated value
+    let name = match line.get(..name_width) {
+        Some(name) if line[name_width..].starts_with(' ') => name.trim_end(),
+        _ => head[0],
+    };
+    let name_columns = name.split_whitespace().count().max(1);
+    let type_ = head
+        .get(name_columns..)
+        .filter(|type_| !type_.is_empty())
+        .ok_or(PDF2ImageError::UnableToParseFontList)?
+        .join(" ");
+
+    Ok(FontInfo {
+        name: name.to_string(),
+        type_,
+        embedded: parse_flag(tail[1])?,
+        subset: parse_flag(tail[2])?,
+        encoding: tail[0].to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Column widths from the pdffonts separator row
+    const NAME_WIDTH: usize = 36;
+
+    #[test]
+    fn parse_embedded_subset_font() {
+        let font = parse_font_line(
+            "ABCDEE+Calibri                       TrueType          WinAnsi          yes yes no       9  0",
+            NAME_WIDTH,
+        )
+        .unwrap();
+
+        assert_eq!(font.name, "ABCDEE+Calibri");
+        assert_eq!(font.type_, "TrueType");
+        assert_eq!(font.encoding, "WinAnsi");
+        assert!(font.embedded);
+        assert!(font.subset);
+    }
+
+    #[test]
+    fn parse_non_embedded_font() {
+        let font = parse_font_line(
+            "Helvetica                            Type 1            Standard         no  no  no      12  0",
+            NAME_WIDTH,
+        )
+        .unwrap();
+
+        assert_eq!(font.name, "Helvetica");
+        assert_eq!(font.type_, "Type 1");
+        assert!(!font.embedded);
+        assert!(!font.subset);
+    }
+
+    #[test]
+    fn parse_font_with_spaces_in_name_and_type() {
+        let font = parse_font_line(
+            "Arial Bold                           CID TrueType      Identity-H       yes no  yes     15  0",
+            NAME_WIDTH,
+        )
+        .unwrap();
+
+        assert_eq!(font.name, "Arial Bold");
+        assert_eq!(font.type_, "CID TrueType");
+        assert_eq!(font.encoding, "Identity-H");
+    }
+
+    #[test]
+    fn parse_truncated_row_fails() {
+        assert!(matches!(
+            parse_font_line("Helvetica Type 1", NAME_WIDTH),
+            Err(PDF2ImageError::UnableToParseFontList)
+        ));
+    }
+}
@@ -1,15 +1,46 @@
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "zip")]
+mod archive;
+mod boxes;
+#[cfg(feature = "cache")]
+mod cache;
+mod concurrency;
 mod error;
+mod fonts;
+mod native;
+mod outline;
 mod pdf;
 mod render_options;
+mod renderer;
+mod split;
+mod version;
 
+#[cfg(feature = "zip")]
+pub use archive::render_pdf_to_zip;
+pub use boxes::{page_boxes, PageBoxes, PdfRect};
+#[cfg(feature = "cache")]
+pub use cache::{render_pdf_single_page_cached, PageCache};
+pub use concurrency::set_global_concurrency;
 pub use error::{PDF2ImageError, Result};
+pub use fonts::{list_fonts, FontInfo};
+pub use native::render_page_native;
+pub use outline::{extract_outline, OutlineItem};
 pub use pdf::{
-    pdftext_all_pages, pdftext_multi_page, pdftext_single_page, render_pdf_multi_page,
-    render_pdf_single_page, Pages, PdfInfo,
+    pdftext_all_pages, pdftext_from_reader, pdftext_multi_page, pdftext_multi_page_numbered,
+    pdftext_single_page, pdftext_stream, render_contact_sheet, render_page_eps, render_page_rgba,
+    render_page_to_writer, render_page_with_text, render_pages_with, render_pdf_multi_page,
+    render_pdf_multi_page_bytes, render_pdf_multi_page_timed, render_pdf_multi_page_with_progress,
+    render_pdf_ps, render_pdf_single_page, render_pdf_single_page_bytes,
+    render_pdf_single_page_with_dpi, Pages, PdfInfo, RenderedPage,
 };
-pub use render_options::{Crop, Password, RenderOptions, RenderOptionsBuilder, Scale, DPI};
+pub use render_options::{
+    content_type_for, Backend, Crop, EolMode, OutputFormat, Password, RenderOptions,
+    RenderOptionsBuilder, RenderOptionsPatch, Scale, ScaleTo, TextEncoding, WarningsSink, DPI,
+};
+pub use renderer::Renderer;
+pub use split::{merge_pdfs, split_pages};
+pub use version::{poppler_version, probe_binaries, PopplerVersion};
 
 // re-export image crate
 pub use image;
@@ -0,0 +1,123 @@
+use tokio::process::Command;
+
+use crate::error::Result;
+use crate::pdf::{get_executable_path, render_pdf_single_page, run_poppler, PdfInfo};
+use crate::render_options::{RenderOptions, DPI};
+
+/// Renders a page at the resolution of its largest embedded image so a
+/// scanned page comes out at the scan's native pixel size without resampling
+///
+/// The resolution is read from pdfimages -list, pages without an embedded
+/// image render at [RenderOptions::resolution]. Any [RenderOptions::scale]
+/// or [RenderOptions::scale_to] is ignored when an image is found
+pub async fn render_page_native(
+    data: &[u8],
+    info: &PdfInfo,
+    page: u32,
+    options: &RenderOptions,
+) -> Result<image::DynamicImage> {
+    let Some(resolution) = native_resolution(data, page, options).await? else {
+        return render_pdf_single_page(data, info, page, options).await;
+    };
+
+    let mut options = options.clone();
+    options.resolution = resolution;
+    options.scale = None;
+    options.scale_to = None;
+
+    render_pdf_single_page(data, info, page, &options).await
+}
+
+/// Finds the resolution of the largest image drawn on the page
+async fn native_resolution(data: &[u8], page: u32, options: &RenderOptions) -> Result<Option<DPI>> {
+    let mut command = Command::new(get_executable_path("pdfimages", options));
+    command.env("LC_ALL", "C").arg("-list").args([
+        "-f",
+        &page.to_string(),
+        "-l",
+        &page.to_string(),
+    ]);
+    if let Some(password) = &options.password {
+        command.args(password.cli_args());
+    }
+    command.arg("-");
+
+    let output = run_poppler(command, data, options).await?;
+    Ok(parse_image_list(&String::from_utf8_lossy(&output)))
+}
+
+/// Parses pdfimages -list output into the resolution of the largest image
+fn parse_image_list(output: &str) -> Option<DPI> {
+    // Rows are reported as
+    // "page num type width height color comp bpc enc interp object ID x-ppi y-ppi size ratio"
+    // below a header and a dashed separator
+    let largest = output
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            if columns.get(2) != Some(&"image") {
+                return None;
+            }
+
+            let width: u64 = columns.get(3)?.parse().ok()?;
+            let height: u64 = columns.get(4)?.parse().ok()?;
+            let x_ppi: f32 = columns.get(12)?.parse().ok()?;
+            let y_ppi: f32 = columns.get(13)?.parse().ok()?;
+
+            Some((width * height, x_ppi, y_ppi))
+        })
+        .filter(|&(_, x_ppi, y_ppi)| x_ppi > 0.0 && y_ppi > 0.0)
+        .max_by_key(|&(pixels, _, _)| pixels);
+
+    largest.map(|(_, x_ppi, y_ppi)| {
+        if x_ppi == y_ppi {
+            DPI::Uniform(x_ppi)
+        } else {
+            DPI::XY(x_ppi, y_ppi)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "\
+page   num  type   width height color comp bpc  enc interp  object ID x-ppi y-ppi size ratio
+--------------------------------------------------------------------------------------------
+";
+
+    #[test]
+    fn parse_image_list_picks_largest_image() {
+        let output = format!(
+            "{HEADER}\
+   1     0 image     100   100  rgb     3   8  jpeg   no        10  0    72    72 1000B 3.3%
+   1     1 smask     2000  2000  gray    1   8  image  no        11  0   600   600 1000B 3.3%
+   1     2 image    2550  3300  rgb     3   8  jpeg   no        12  0   300   300  100K 1.2%
+"
+        );
+
+        assert!(matches!(parse_image_list(&output), Some(DPI::Uniform(dpi)) if dpi == 300.0));
+    }
+
+    #[test]
+    fn parse_image_list_reports_anisotropic_resolution() {
+        let output = format!(
+            "{HEADER}\
+   1     0 image    1700  2200  gray    1   1  ccitt  no        10  0   200   100  10K 0.5%
+"
+        );
+
+        assert!(matches!(
+            parse_image_list(&output),
+            Some(DPI::XY(x, y)) if x == 200.0 && y == 100.0
+        ));
+    }
+
+    #[test]
+    fn parse_image_list_without_images() {
+        assert!(parse_image_list(HEADER).is_none());
+        assert!(parse_image_list("").is_none());
+    }
+}
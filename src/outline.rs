@@ -0,0 +1,232 @@
+use tokio::process::Command;
+
+use crate::error::Result;
+use crate::pdf::{get_executable_path, run_poppler_checked};
+use crate::render_options::RenderOptions;
+
+/// An entry in the document outline (bookmarks)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineItem {
+    /// The displayed title of the entry
+    pub title: String,
+    /// The page the entry links to, `None` when it doesn't link to a page
+    pub page: Option<u32>,
+    /// The nesting depth, 0 for top level entries
+    pub level: u32,
+    /// The entries nested beneath this one
+    pub children: Vec<OutlineItem>,
+}
+
+/// Extracts the document outline (bookmarks) using pdftohtml -xml
+///
+/// pdftohtml can't read the PDF from stdin so it is written to a temporary
+/// directory within [RenderOptions::temp_dir_path] which is removed
+/// afterwards. Returns an empty list when the PDF has no outline
+pub async fn extract_outline(data: &[u8], options: &RenderOptions) -> Result<Vec<OutlineItem>> {
+    let dir = tempfile::Builder::new()
+        .prefix("pdf2image-")
+        .tempdir_in(options.temp_dir_path())?;
+    let input = dir.path().join("input.pdf");
+    tokio::fs::write(&input, data).await?;
+
+    let mut command = Command::new(get_executable_path("pdftohtml", options));
+    command
+        // Write the xml to stdout skipping images
+        .args(["-xml", "-stdout", "-i", "-q"]);
+    if let Some(password) = &options.password {
+        command.args(password.cli_args());
+    }
+    command.arg(&input);
+
+    let output = run_poppler_checked(command, tokio::io::empty(), options, "pdftohtml").await?;
+
+    Ok(parse_outline(&String::from_utf8_lossy(&output)))
+}
+
+/// Parses the nested `<outline>` lists pdftohtml appends to the xml,
+/// a nested list holds the children of the item preceding it
+///
+/// ```xml
+/// <outline>
+/// <item page="1">Chapter 1</item>
+/// <outline>
+/// <item page="2">Section 1.1</item>
+/// </outline>
+/// </outline>
+/// ```
+fn parse_outline(xml: &str) -> Vec<OutlineItem> {
+    let Some(start) = xml.find("<outline") else {
+        return Vec::new();
+    };
+
+    // The items of each currently open list, innermost last
+    let mut lists: Vec<Vec<OutlineItem>> = Vec::new();
+    let mut rest = &xml[start..];
+
+    while let Some(tag_start) = rest.find('<') {
+        rest = &rest[tag_start..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+
+        if rest.starts_with("<outline") {
+            lists.push(Vec::new());
+        } else if rest.starts_with("</outline>") {
+            let items = lists.pop().unwrap_or_default();
+            match lists.last_mut() {
+                Some(parent) => match parent.last_mut() {
+                    Some(item) => item.children.extend(items),
+                    None => parent.extend(items),
+                },
+                // The top level list is complete
+                None => return items,
+            }
+        } else if rest.starts_with("<item") {
+            let Some(close) = rest.find("</item>") else {
+                break;
+            };
+
+            let page = attribute(&rest[..tag_end], "page").and_then(|page| page.parse().ok());
+            let level = u32::try_from(lists.len().saturating_sub(1)).unwrap_or(u32::MAX);
+
+            if let Some(items) = lists.last_mut() {
+                items.push(OutlineItem {
+                    title: unescape(rest[tag_end + 1..close].trim()),
+                    page,
+                    level,
+                    children: Vec::new(),
+                });
+            }
+
+            rest = &rest[close + "</item>".len()..];
+            continue;
+        }
+
+        rest = &rest[tag_end + 1..];
+    }
+
+    lists.into_iter().next().unwrap_or_default()
+}
+
+/// The value of the named attribute within the opening tag
+fn attribute<'tag>(tag: &'tag str, name: &str) -> Option<&'tag str> {
+    let (_, value) = tag.split_once(&format!(" {name}=\""))?;
+    value.split_once('"').map(|(value, _)| value)
+}
+
+/// Replaces the xml entity and character references in the text
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+
+        let replacement = match &rest[1..end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            reference => reference
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| reference.strip_prefix('#').map(str::parse))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32),
+        };
+
+        match replacement {
+            Some(character) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            // Leave unknown references as they are
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, page: Option<u32>, level: u32, children: Vec<OutlineItem>) -> OutlineItem {
+        OutlineItem {
+            title: title.to_string(),
+            page,
+            level,
+            children,
+        }
+    }
+
+    #[test]
+    fn parse_nested_outline() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<pdf2xml producer="poppler" version="22.02.0">
+<page number="1" position="absolute" top="0" left="0" height="1188" width="918">
+</page>
+<outline>
+<item page="1">Chapter 1</item>
+<outline>
+<item page="2">Section 1.1</item>
+<item page="3">Section 1.2</item>
+<outline>
+<item page="3">Detail</item>
+</outline>
+</outline>
+<item page="4">Q&amp;A &#8211; &#x41;ppendix</item>
+<item>Unlinked</item>
+</outline>
+</pdf2xml>
+"#;
+
+        assert_eq!(
+            parse_outline(xml),
+            [
+                item(
+                    "Chapter 1",
+                    Some(1),
+                    0,
+                    vec![
+                        item("Section 1.1", Some(2), 1, vec![]),
+                        item(
+                            "Section 1.2",
+                            Some(3),
+                            1,
+                            vec![item("Detail", Some(3), 2, vec![])]
+                        ),
+                    ]
+                ),
+                item("Q&A \u{2013} Appendix", Some(4), 0, vec![]),
+                item("Unlinked", None, 0, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_without_outline() {
+        assert!(parse_outline("<pdf2xml><page number=\"1\"></page></pdf2xml>").is_empty());
+    }
+
+    #[test]
+    fn unescape_references() {
+        assert_eq!(
+            unescape("a &lt;b&gt; &quot;c&quot; &apos;d&apos;"),
+            "a <b> \"c\" 'd'"
+        );
+        assert_eq!(unescape("&#233;&#xe9;"), "\u{e9}\u{e9}");
+        assert_eq!(unescape("&unknown; & done"), "&unknown; & done");
+    }
+}
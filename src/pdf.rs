@@ -1,28 +1,147 @@
-use futures::{future::BoxFuture, stream::FuturesOrdered, TryStreamExt};
+use futures::{
+    future::BoxFuture,
+    stream::{FuturesOrdered, Stream, StreamExt},
+    TryStreamExt,
+};
+use std::collections::HashMap;
 use std::process::Stdio;
-use tokio::io::AsyncWriteExt;
-use tokio::process::Command;
+use std::time::{Duration, Instant};
 
+use image::GenericImageView;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStderr, Command};
+
+use crate::concurrency::acquire_process_slot;
 use crate::error::{PDF2ImageError, Result};
-use crate::render_options::RenderOptions;
+use crate::render_options::{Backend, Password, RenderOptions, Scale, TextEncoding, DPI};
+use crate::version::{require_version, PopplerVersion};
+
+/// The first poppler release whose pdftoppm accepts -hide-annotations
+const HIDE_ANNOTATIONS_VERSION: PopplerVersion = PopplerVersion::new(0, 60, 0);
 
+/// Basic information about a PDF read using pdfinfo
+///
+/// Use [PdfInfo::read] from async code, the blocking [TryFrom<&[u8]>]
+/// implementation (requires the `blocking` feature) is provided for
+/// synchronous callers and must not be used from within an async runtime
 pub struct PdfInfo {
     /// The page count within the pdf
     page_count: u32,
     /// Whether the PDF is encrypted
     encrypted: bool,
+    /// Whether the PDF is linearized ("fast web view")
+    linearized: bool,
+    /// The PDF version as (major, minor)
+    pdf_version: (u8, u8),
+    /// Whether the PDF is tagged for accessibility
+    tagged: bool,
+    /// The (width, height) of each page MediaBox in points with the rotation applied
+    page_sizes: Vec<(f64, f64)>,
+    /// The application that produced the PDF
+    producer: Option<String>,
+    /// The application that created the original document
+    creator: Option<String>,
+    /// Whether poppler had to reconstruct the xref table to read the PDF
+    damaged: bool,
+    /// Whether a JavaScript name was found in the raw file bytes
+    javascript: bool,
+    /// Whether an AcroForm name was found in the raw file bytes
+    acroform: bool,
+    /// The size of a user space unit in multiples of 1/72 inch
+    user_unit: f64,
+    /// Every document level "Key: Value" pair pdfinfo reported
+    raw_fields: HashMap<String, String>,
 }
 
+/// Lowercase words of producer/creator strings written by scanner
+/// drivers and scanning software
+const SCANNER_PRODUCERS: &[&str] = &[
+    "scan",
+    "scanner",
+    "scansnap",
+    "canon",
+    "epson",
+    "fujitsu",
+    "xerox",
+    "kodak",
+    "ricoh",
+    "kyocera",
+    "konica",
+    "minolta",
+    "brother",
+    "paperport",
+    "naps2",
+];
+
 impl PdfInfo {
+    /// Reads the PDF info from the provided pdf file data
     pub async fn read(data: &[u8]) -> Result<Self> {
-        let (page_count, encrypted) = extract_pdf_info(data).await?;
+        extract_pdf_info(data, &RenderOptions::default()).await
+    }
 
-        Ok(Self {
-            page_count,
-            encrypted,
+    /// Reads the PDF info using the executable overrides, password
+    /// and warnings sink from the provided `options`
+    pub async fn read_with_options(data: &[u8], options: &RenderOptions) -> Result<Self> {
+        extract_pdf_info(data, options).await
+    }
+
+    /// Checks whether the password unlocks the PDF by running pdfinfo with it
+    ///
+    /// The `password` is used in place of any [RenderOptions::password],
+    /// the executable overrides and environment of the `options` apply
+    ///
+    /// Returns `Ok(false)` when poppler reports an incorrect password, other
+    /// failures such as a missing executable or an unreadable file are errors
+    pub async fn verify_password(
+        data: &[u8],
+        password: &Password,
+        options: &RenderOptions,
+    ) -> Result<bool> {
+        let mut command = Command::new(get_executable_path("pdfinfo", options));
+        command.args(password.cli_args()).arg("-");
+
+        let output = run_poppler_output(command, data, options, true).await?;
+        if output.status.success() {
+            return Ok(true);
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("Incorrect password") {
+            return Ok(false);
+        }
+
+        Err(PDF2ImageError::CommandFailed {
+            executable: "pdfinfo",
+            status: output.status,
+            stderr: stderr.trim().to_string(),
         })
     }
 
+    /// Reads the PDF info of an encrypted PDF using the provided password
+    pub async fn read_with_password(data: &[u8], password: &Password) -> Result<Self> {
+        let options = RenderOptions {
+            password: Some(password.clone()),
+            ..RenderOptions::default()
+        };
+
+        extract_pdf_info(data, &options).await
+    }
+
+    /// Buffers the PDF from the `reader` and reads its info, returning the
+    /// buffered bytes alongside for use with the render functions
+    ///
+    /// pdfinfo and every render need the whole file, so the input is
+    /// buffered once here and the returned bytes should be reused rather
+    /// than reading the source again
+    pub async fn read_from(mut reader: impl AsyncRead + Unpin) -> Result<(Self, Vec<u8>)> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+
+        let info = PdfInfo::read(&data).await?;
+
+        Ok((info, data))
+    }
+
     /// Returns the number of pages in the PDF.
     pub fn page_count(&self) -> u32 {
         self.page_count
@@ -32,6 +151,165 @@ impl PdfInfo {
     pub fn is_encrypted(&self) -> bool {
         self.encrypted
     }
+
+    /// Returns whether the PDF is linearized (optimized for "fast web view").
+    pub fn is_linearized(&self) -> bool {
+        self.linearized
+    }
+
+    /// Returns the PDF version as (major, minor), defaults to 1.0
+    /// when the version is not reported
+    pub fn pdf_version(&self) -> (u8, u8) {
+        self.pdf_version
+    }
+
+    /// Returns whether the PDF is tagged for accessibility, defaults
+    /// to false when not reported (older poppler versions omit this)
+    pub fn is_tagged(&self) -> bool {
+        self.tagged
+    }
+
+    /// Returns the (width, height) of the page MediaBox in points with the
+    /// page rotation applied, the area poppler renders when no
+    /// [RenderOptions::crop] is set
+    pub fn page_size(&self, page: u32) -> Option<(f64, f64)> {
+        let index = usize::try_from(page.checked_sub(1)?).ok()?;
+        self.page_sizes.get(index).copied()
+    }
+
+    /// Returns whether poppler reported the PDF as damaged while reading it
+    ///
+    /// Poppler can usually reconstruct such files and still render them,
+    /// the reported warnings are sent to the [RenderOptions::warnings_sink]
+    pub fn is_damaged(&self) -> bool {
+        self.damaged
+    }
+
+    /// Best-effort check for embedded JavaScript
+    ///
+    /// The raw file bytes are scanned for the `/JavaScript` and `/JS` names,
+    /// compressed object streams are not decoded so JavaScript hidden inside
+    /// them is missed. Treat `false` as "none found" rather than "none present"
+    pub fn has_javascript(&self) -> bool {
+        self.javascript
+    }
+
+    /// Best-effort check for an interactive form, scanning the raw file
+    /// bytes for the `/AcroForm` name with the same limitations as
+    /// [PdfInfo::has_javascript]
+    pub fn has_acroform(&self) -> bool {
+        self.acroform
+    }
+
+    /// Returns the size of a user space unit in multiples of 1/72 inch,
+    /// 1.0 when the PDF doesn't set a /UserUnit
+    ///
+    /// Read from pdfinfo when reported, otherwise from the first /UserUnit
+    /// found in the raw file bytes, so a document mixing different units
+    /// across pages reports only one of them
+    pub fn user_unit(&self) -> f64 {
+        self.user_unit
+    }
+
+    /// Returns every document level "Key: Value" pair reported by pdfinfo
+    /// with the key and value trimmed (e.g. "Pages" -> "12"), the per page
+    /// size and rotation lines are not included
+    ///
+    /// Useful for fields without a dedicated accessor, the available keys
+    /// depend on the poppler version and the document
+    pub fn raw_fields(&self) -> &HashMap<String, String> {
+        &self.raw_fields
+    }
+
+    /// Returns the producer metadata entry, if present
+    pub fn producer(&self) -> Option<&str> {
+        self.producer.as_deref()
+    }
+
+    /// Returns the creator metadata entry, if present
+    pub fn creator(&self) -> Option<&str> {
+        self.creator.as_deref()
+    }
+
+    /// Whether the producer or creator names a known scanner vendor or
+    /// scanning application (e.g. "Canon", "HP Scan", "ScanSnap")
+    ///
+    /// Only whole words are matched so "Canonical" isn't taken for "Canon".
+    /// The metadata is freely editable and many tools overwrite it, so expect
+    /// both false positives and false negatives
+    pub fn has_scanner_producer(&self) -> bool {
+        [self.producer(), self.creator()]
+            .into_iter()
+            .flatten()
+            .map(str::to_lowercase)
+            .any(|value| {
+                value
+                    .split(|c: char| !c.is_alphanumeric())
+                    .any(|word| SCANNER_PRODUCERS.contains(&word))
+            })
+    }
+
+    /// Best-effort guess at whether the PDF came from a scanner, combining
+    /// [PdfInfo::has_scanner_producer] with the PDF using no fonts (no
+    /// embedded text), listed with pdffonts
+    ///
+    /// Scans that were run through OCR carry a text layer and its fonts, so
+    /// they are not reported as scans
+    pub async fn looks_like_scan(&self, data: &[u8], options: &RenderOptions) -> Result<bool> {
+        if !self.has_scanner_producer() {
+            return Ok(false);
+        }
+
+        Ok(crate::fonts::list_fonts(data, options).await?.is_empty())
+    }
+}
+
+#[cfg(test)]
+impl PdfInfo {
+    /// Info for an unencrypted PDF with `page_count` US letter pages
+    pub(crate) fn with_page_count(page_count: u32) -> Self {
+        Self {
+            page_count,
+            encrypted: false,
+            linearized: false,
+            pdf_version: (1, 7),
+            tagged: false,
+            page_sizes: vec![(612.0, 792.0); page_count as usize],
+            producer: None,
+            creator: None,
+            damaged: false,
+            javascript: false,
+            acroform: false,
+            user_unit: 1.0,
+            raw_fields: HashMap::new(),
+        }
+    }
+}
+
+/// A rendered page along with the resolution it was rendered at
+pub struct RenderedPage {
+    /// The rendered page image
+    pub image: image::DynamicImage,
+    /// The effective (x, y) resolution in dots per inch, derived from the
+    /// page size and image dimensions, `None` when the page size is unknown
+    pub dpi: Option<(f64, f64)>,
+}
+
+#[cfg(feature = "blocking")]
+impl TryFrom<&[u8]> for PdfInfo {
+    type Error = PDF2ImageError;
+
+    /// Reads the PDF info blocking the current thread until complete
+    ///
+    /// # Panics
+    ///
+    /// Panics when called from within an async runtime, use [PdfInfo::read] instead
+    fn try_from(data: &[u8]) -> Result<Self> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()?
+            .block_on(PdfInfo::read(data))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +318,78 @@ pub enum Pages {
     All,
     Range(std::ops::RangeInclusive<u32>),
     Specific(Vec<u32>),
+    /// Every even numbered page
+    Even,
+    /// Every odd numbered page
+    Odd,
+    /// The final N pages, all pages when N exceeds the page count
+    Last(u32),
+}
+
+impl Pages {
+    /// Expands the selection into the page numbers that will be rendered,
+    /// pages outside of `1..=page_count` are dropped
+    pub fn resolve(&self, info: &PdfInfo) -> Vec<u32> {
+        let valid_range = 1..=info.page_count;
+
+        match self {
+            Pages::All => valid_range.collect(),
+            Pages::Range(range) => range // Filter only valid pages
+                .clone()
+                .filter(|value| valid_range.contains(value))
+                .collect(),
+            Pages::Specific(pages) => pages // Filter only valid pages
+                .iter()
+                .copied()
+                .filter(|value| valid_range.contains(value))
+                .collect(),
+            Pages::Even => valid_range.filter(|page| page % 2 == 0).collect(),
+            Pages::Odd => valid_range.filter(|page| page % 2 == 1).collect(),
+            Pages::Last(count) => {
+                let first = info.page_count.saturating_sub(*count) + 1;
+                (first..=info.page_count).collect()
+            }
+        }
+    }
+
+    /// Like [Pages::resolve] but fails with [PDF2ImageError::PageOutOfRange]
+    /// for the first requested page outside of `1..=page_count` instead of
+    /// dropping it
+    pub fn resolve_strict(&self, info: &PdfInfo) -> Result<Vec<u32>> {
+        let valid_range = 1..=info.page_count;
+        let out_of_range = match self {
+            Pages::Range(range) => range.clone().find(|page| !valid_range.contains(page)),
+            Pages::Specific(pages) => pages
+                .iter()
+                .copied()
+                .find(|page| !valid_range.contains(page)),
+            // The remaining selections only ever produce valid pages
+            _ => None,
+        };
+
+        if let Some(page) = out_of_range {
+            return Err(PDF2ImageError::PageOutOfRange {
+                page,
+                page_count: info.page_count,
+            });
+        }
+
+        Ok(self.resolve(info))
+    }
+
+    /// Resolves the pages leniently or strictly depending on
+    /// [RenderOptions::strict_pages]
+    pub(crate) fn resolve_with_options(
+        &self,
+        info: &PdfInfo,
+        options: &RenderOptions,
+    ) -> Result<Vec<u32>> {
+        if options.strict_pages {
+            return self.resolve_strict(info);
+        }
+
+        Ok(self.resolve(info))
+    }
 }
 
 /// Renders the PDF to images.
@@ -53,299 +403,2276 @@ pub async fn render_pdf_single_page<'data, 'options: 'data>(
         return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
     }
 
-    let image = render_page(data, page, options).await?;
+    let image = render_page(data, info, page, options).await?;
 
     Ok(image)
 }
 
-/// Renders the PDF to images.
-pub async fn render_pdf_multi_page<'data, 'options: 'data>(
+/// Renders a single page of the PDF to a tightly packed RGBA8 pixel
+/// buffer returned along with its (width, height)
+pub async fn render_page_rgba<'data, 'options: 'data>(
     data: &'data [u8],
     info: &'options PdfInfo,
-    pages: Pages,
+    page: u32,
     options: &'options RenderOptions,
-) -> Result<Vec<image::DynamicImage>> {
+) -> Result<(Vec<u8>, u32, u32)> {
+    let image = render_pdf_single_page(data, info, page, options)
+        .await?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+
+    Ok((image.into_raw(), width, height))
+}
+
+/// Renders a single page of the PDF to encoded image bytes in the
+/// format given by [RenderOptions::output_format]
+///
+/// When [RenderOptions::deterministic] is set the output is byte-stable
+/// for identical inputs
+pub async fn render_pdf_single_page_bytes<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    page: u32,
+    options: &'options RenderOptions,
+) -> Result<Vec<u8>> {
     if info.encrypted && options.password.is_none() {
         return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
     }
 
-    let valid_range = 0..=info.page_count;
+    render_page_bytes(data, info, page, options).await
+}
 
-    let pages_range: Vec<u32> = match pages {
-        Pages::All => valid_range.collect(),
-        Pages::Range(range) => range // Filter only valid pages
-            .filter(|value| valid_range.contains(value))
-            .collect(),
-        Pages::Specific(pages) => pages // Filter only valid pages
-            .into_iter()
-            .filter(|value| valid_range.contains(value))
-            .collect(),
-    };
+/// Renders multiple pages of the PDF to encoded image bytes in the
+/// format given by [RenderOptions::output_format]
+///
+/// Use this to bypass the built in decoding and decode the pages yourself
+pub async fn render_pdf_multi_page_bytes<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    pages: Pages,
+    options: &'options RenderOptions,
+) -> Result<Vec<Vec<u8>>> {
+    if info.encrypted && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
+
+    let pages_range = pages.resolve_with_options(info, options)?;
 
     pages_range
         .into_iter()
-        .map(|page| -> BoxFuture<'data, Result<image::DynamicImage>> {
-            Box::pin(render_page(data, page, options))
+        .map(|page| -> BoxFuture<'data, Result<Vec<u8>>> {
+            Box::pin(async move {
+                render_page_bytes(data, info, page, options)
+                    .await
+                    .map_err(|err| err.with_page(page))
+            })
         })
-        .collect::<FuturesOrdered<BoxFuture<'data, Result<image::DynamicImage>>>>()
+        .collect::<FuturesOrdered<BoxFuture<'data, Result<Vec<u8>>>>>()
         .try_collect()
         .await
 }
 
-/// Renders a specific page from the pdf file
-async fn render_page<'data, 'options: 'data>(
-    data: &'data [u8],
-    page: u32,
-    options: &'options RenderOptions,
+/// Renders every page as a thumbnail laid out in a grid on a single
+/// image, filling rows left to right with `columns` cells per row
+///
+/// Each page is scaled to fit within the (width, height) `cell` keeping
+/// its aspect ratio and centered on a white background, a `columns`
+/// value of 0 is treated as 1
+///
+/// Empty cells and sheets too large to allocate are rejected with
+/// [PDF2ImageError::InvalidOptions], [RenderOptions::max_pixels] limits
+/// the size of the whole sheet
+pub async fn render_contact_sheet(
+    data: &[u8],
+    info: &PdfInfo,
+    options: &RenderOptions,
+    columns: u32,
+    cell: (u32, u32),
 ) -> Result<image::DynamicImage> {
-    let cli_options = options.to_cli_args();
+    let (cell_width, cell_height) = cell;
+    let columns = columns.max(1);
+    let (sheet_width, sheet_height) = contact_sheet_size(info.page_count, columns, cell)?;
 
-    let executable = get_executable_path(if options.pdftocairo {
-        "pdftocairo"
-    } else {
-        "pdftoppm"
-    });
+    if let Some(limit) = options.max_pixels {
+        let pixels = u64::from(sheet_width) * u64::from(sheet_height);
+        if pixels > limit {
+            return Err(PDF2ImageError::OutputTooLarge { pixels, limit });
+        }
+    }
 
-    let poppler_args: &[&str] = if options.pdftocairo {
-        &["-", "-", "-jpeg", "-singlefile"]
-    } else {
-        &["-jpeg", "-singlefile"]
+    // Let poppler render close to the thumbnail size rather than at full resolution
+    let mut thumbnail_options = options.clone();
+    thumbnail_options.scale = Some(Scale::Uniform(cell_width.max(cell_height).max(1)));
+    thumbnail_options.scale_to = None;
+    thumbnail_options.resize = None;
+
+    let pages = render_pdf_multi_page(data, info, Pages::All, &thumbnail_options).await?;
+
+    let mut sheet =
+        image::RgbaImage::from_pixel(sheet_width, sheet_height, image::Rgba([255, 255, 255, 255]));
+
+    for (index, page) in (0u32..).zip(pages) {
+        let thumbnail = page.thumbnail(cell_width, cell_height);
+        let x = (index % columns) * cell_width + (cell_width - thumbnail.width()) / 2;
+        let y = (index / columns) * cell_height + (cell_height - thumbnail.height()) / 2;
+
+        image::imageops::overlay(&mut sheet, &thumbnail, i64::from(x), i64::from(y));
+    }
+
+    Ok(image::DynamicImage::ImageRgba8(sheet))
+}
+
+/// The (width, height) of a contact sheet holding `pages` cells in rows
+/// of `columns`
+fn contact_sheet_size(
+    pages: u32,
+    columns: u32,
+    (cell_width, cell_height): (u32, u32),
+) -> Result<(u32, u32)> {
+    let invalid = |reason: &str| PDF2ImageError::InvalidOptions {
+        field: "cell",
+        reason: reason.to_string(),
     };
 
-    let mut child = Command::new(&executable)
-        // Add the poppler args
-        .args(poppler_args)
-        // Add the page args
-        .args([
-            "-f".to_string(),
-            format!("{page}"),
-            "-l".to_string(),
-            format!("{page}"),
-        ])
-        // Add the cli options
-        .args(cli_options)
-        // Pipe input and output for use
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()?;
+    if cell_width == 0 || cell_height == 0 {
+        return Err(invalid("must have a non-zero width and height"));
+    }
 
-    // UNWRAP SAFETY: The child process is guaranteed to have a stdin as .stdin(Stdio::piped()) was called
-    child.stdin.as_mut().unwrap().write_all(data).await?;
+    let rows = pages.div_ceil(columns);
+    let size = columns
+        .checked_mul(cell_width)
+        .zip(rows.checked_mul(cell_height))
+        // The sheet is allocated as 4 bytes per pixel
+        .filter(|&(width, height)| {
+            u64::from(width)
+                .checked_mul(u64::from(height))
+                .and_then(|pixels| pixels.checked_mul(4))
+                .and_then(|bytes| usize::try_from(bytes).ok())
+                .is_some_and(|bytes| bytes <= isize::MAX as usize)
+        });
 
-    let output = child.wait_with_output().await?;
-    let image = image::load_from_memory_with_format(&output.stdout, image::ImageFormat::Jpeg)?;
+    size.ok_or_else(|| invalid("the contact sheet is too large"))
+}
 
-    Ok(image)
+/// Renders a single page of the PDF streaming the encoded image bytes
+/// poppler produces into the `writer`, returning the number of bytes written
+///
+/// The bytes are in the format given by [RenderOptions::output_format] as
+/// produced by poppler, unlike [render_pdf_single_page_bytes] they are not
+/// normalized when [RenderOptions::deterministic] is set and the
+/// [RenderOptions::fallback_to_pdftoppm] fallback is not applied
+///
+/// Formats poppler can't write (WebP) are buffered and re-encoded first
+pub async fn render_page_to_writer<W>(
+    data: &[u8],
+    info: &PdfInfo,
+    page: u32,
+    options: &RenderOptions,
+    writer: &mut W,
+) -> Result<u64>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    if info.encrypted && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
+
+    // Re-encoded formats can't be streamed straight from poppler
+    if options.output_format() != options.poppler_format() {
+        let output = render_page_bytes(data, info, page, options).await?;
+        writer.write_all(&output).await?;
+        return Ok(output.len() as u64);
+    }
+
+    let adjusted = effective_options(info, page, options);
+    let options = adjusted.as_ref().unwrap_or(options);
+
+    check_max_pixels(info, page, options)?;
+
+    let command = render_page_command(page, options, options.backend()).await?;
+
+    run_poppler_to_writer(command, data, options, writer).await
 }
 
-/// Extracts the text contents of a pdf file from a single page
-pub async fn pdftext_single_page<'data, 'options: 'data>(
+/// Renders a single page of the PDF to an image while extracting its
+/// text, running the renderer and pdftotext concurrently
+pub async fn render_page_with_text<'data, 'options: 'data>(
     data: &'data [u8],
     info: &'options PdfInfo,
     page: u32,
     options: &'options RenderOptions,
-) -> Result<String> {
+) -> Result<(image::DynamicImage, String)> {
     if info.encrypted && options.password.is_none() {
         return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
     }
 
-    let image = render_page_text(data, page, options).await?;
-
-    Ok(image)
+    futures::try_join!(
+        render_page(data, info, page, options),
+        render_page_text(data, page, options)
+    )
 }
 
-/// Extracts the text contents of a pdf file from multiple page
+/// Renders a single page of the PDF to an image along with the
+/// effective resolution poppler rendered it at, useful when the
+/// resolution is chosen by poppler through scaling
 ///
-/// If you want all pages as a string it will likely be more performant
-/// to use [pdftext_multi_page]
-pub async fn pdftext_multi_page<'data, 'options: 'data>(
+/// The resolution is derived from the full page size so it does not
+/// account for a [RenderOptions::crop]
+pub async fn render_pdf_single_page_with_dpi<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    page: u32,
+    options: &'options RenderOptions,
+) -> Result<RenderedPage> {
+    let image = render_pdf_single_page(data, info, page, options).await?;
+
+    let dpi = info.page_size(page).map(|(width, height)| {
+        (
+            f64::from(image.width()) * 72.0 / width,
+            f64::from(image.height()) * 72.0 / height,
+        )
+    });
+
+    Ok(RenderedPage { image, dpi })
+}
+
+/// Renders the PDF to images.
+pub async fn render_pdf_multi_page<'data, 'options: 'data>(
     data: &'data [u8],
     info: &'options PdfInfo,
     pages: Pages,
     options: &'options RenderOptions,
-) -> Result<String> {
+) -> Result<Vec<image::DynamicImage>> {
     if info.encrypted && options.password.is_none() {
         return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
     }
 
-    let valid_range = 0..=info.page_count;
-
-    let pages_range: Vec<u32> = match pages {
-        Pages::All => valid_range.collect(),
-        Pages::Range(range) => range // Filter only valid pages
-            .filter(|value| valid_range.contains(value))
-            .collect(),
-        Pages::Specific(pages) => pages // Filter only valid pages
-            .into_iter()
-            .filter(|value| valid_range.contains(value))
-            .collect(),
-    };
+    let pages_range = pages.resolve_with_options(info, options)?;
 
     pages_range
         .into_iter()
-        .map(|page| -> BoxFuture<'data, Result<String>> {
-            Box::pin(render_page_text(data, page, options))
+        .map(|page| -> BoxFuture<'data, Result<image::DynamicImage>> {
+            Box::pin(async move {
+                render_page(data, info, page, options)
+                    .await
+                    .map_err(|err| err.with_page(page))
+            })
         })
-        .collect::<FuturesOrdered<BoxFuture<'data, Result<String>>>>()
+        .collect::<FuturesOrdered<BoxFuture<'data, Result<image::DynamicImage>>>>()
         .try_collect()
         .await
 }
 
-/// Extracts the text contents of a pdf file from all pages as
-/// one big string, use [pdftext_multi_page] to get a separate
-/// string for each page
-pub async fn pdftext_all_pages<'data, 'options: 'data>(
+/// Renders each listed page with its own options, e.g. a high resolution
+/// cover followed by low resolution body pages
+///
+/// Pages render concurrently and the images are returned in the order of
+/// `specs`
+pub async fn render_pages_with(
+    data: &[u8],
+    info: &PdfInfo,
+    specs: Vec<(u32, RenderOptions)>,
+) -> Result<Vec<image::DynamicImage>> {
+    specs
+        .iter()
+        .map(
+            |(page, options)| -> BoxFuture<'_, Result<image::DynamicImage>> {
+                Box::pin(async move {
+                    if info.encrypted && options.password.is_none() {
+                        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+                    }
+
+                    render_page(data, info, *page, options)
+                        .await
+                        .map_err(|err| err.with_page(*page))
+                })
+            },
+        )
+        .collect::<FuturesOrdered<BoxFuture<'_, Result<image::DynamicImage>>>>()
+        .try_collect()
+        .await
+}
+
+/// Renders the PDF to images along with the wall time spent rendering
+/// each page, useful for finding pathological pages
+///
+/// Pages render concurrently so each duration is measured around its own
+/// render and the durations may overlap
+pub async fn render_pdf_multi_page_timed<'data, 'options: 'data>(
     data: &'data [u8],
     info: &'options PdfInfo,
     pages: Pages,
     options: &'options RenderOptions,
-) -> Result<String> {
+) -> Result<Vec<(image::DynamicImage, Duration)>> {
     if info.encrypted && options.password.is_none() {
         return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
     }
 
-    let valid_range = 0..=info.page_count;
-
-    let pages_range: Vec<u32> = match pages {
-        Pages::All => return render_all_pages_text(data, options).await,
-        Pages::Range(range) => range // Filter only valid pages
-            .filter(|value| valid_range.contains(value))
-            .collect(),
-        Pages::Specific(pages) => pages // Filter only valid pages
-            .into_iter()
-            .filter(|value| valid_range.contains(value))
-            .collect(),
-    };
+    let pages_range = pages.resolve_with_options(info, options)?;
 
     pages_range
         .into_iter()
-        .map(|page| -> BoxFuture<'data, Result<String>> {
-            Box::pin(render_page_text(data, page, options))
-        })
-        .collect::<FuturesOrdered<BoxFuture<'data, Result<String>>>>()
+        .map(
+            |page| -> BoxFuture<'data, Result<(image::DynamicImage, Duration)>> {
+                Box::pin(async move {
+                    let started = Instant::now();
+                    let image = render_page(data, info, page, options)
+                        .await
+                        .map_err(|err| err.with_page(page))?;
+                    Ok((image, started.elapsed()))
+                })
+            },
+        )
+        .collect::<FuturesOrdered<BoxFuture<'data, Result<(image::DynamicImage, Duration)>>>>()
         .try_collect()
         .await
 }
 
-/// Renders a specific page from the pdf file
-async fn render_page_text<'data, 'options: 'data>(
+/// Renders the PDF to images calling `on_progress` with the number of
+/// completed pages and the total number of pages as each page completes
+///
+/// The callback runs on the executor while rendering so it should return
+/// quickly, forward the progress elsewhere if it needs to do any real work
+pub async fn render_pdf_multi_page_with_progress<'data, 'options: 'data, F>(
     data: &'data [u8],
-    page: u32,
+    info: &'options PdfInfo,
+    pages: Pages,
     options: &'options RenderOptions,
-) -> Result<String> {
-    let cli_options = options.to_cli_args();
+    on_progress: F,
+) -> Result<Vec<image::DynamicImage>>
+where
+    F: Fn(usize, usize),
+{
+    if info.encrypted && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
 
-    let mut child = Command::new("pdftotext")
-        // Take input from stdin and provide to stdout
-        .args(["-", "-"])
-        // Add the page args
-        .args([
-            "-f".to_string(),
-            format!("{page}"),
-            "-l".to_string(),
-            format!("{page}"),
-        ])
-        // Add the cli options
-        .args(cli_options)
-        // Pipe input and output for use
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()?;
+    let pages_range = pages.resolve_with_options(info, options)?;
 
-    // UNWRAP SAFETY: The child process is guaranteed to have a stdin as .stdin(Stdio::piped()) was called
-    child.stdin.as_mut().unwrap().write_all(data).await?;
+    let total = pages_range.len();
 
-    let output = child.wait_with_output().await?;
-    let value = String::from_utf8_lossy(&output.stdout);
+    let mut renders = pages_range
+        .into_iter()
+        .map(|page| -> BoxFuture<'data, Result<image::DynamicImage>> {
+            Box::pin(async move {
+                render_page(data, info, page, options)
+                    .await
+                    .map_err(|err| err.with_page(page))
+            })
+        })
+        .collect::<FuturesOrdered<BoxFuture<'data, Result<image::DynamicImage>>>>();
 
-    Ok(value.into_owned())
-}
-/// Renders a specific page from the pdf file
-async fn render_all_pages_text<'data, 'options: 'data>(
-    data: &'data [u8],
-    options: &'options RenderOptions,
-) -> Result<String> {
-    let cli_options = options.to_cli_args();
+    let mut images = Vec::with_capacity(total);
 
-    let mut child = Command::new("pdftotext")
-        // Take input from stdin and provide to stdout
-        .args(["-", "-"])
-        // Add the cli options
-        .args(cli_options)
-        // Pipe input and output for use
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()?;
+    while let Some(image) = renders.try_next().await? {
+        images.push(image);
+        on_progress(images.len(), total);
+    }
 
-    // UNWRAP SAFETY: The child process is guaranteed to have a stdin as .stdin(Stdio::piped()) was called
-    child.stdin.as_mut().unwrap().write_all(data).await?;
+    Ok(images)
+}
 
-    let output = child.wait_with_output().await?;
-    let value = String::from_utf8_lossy(&output.stdout);
+/// Renders a specific page from the pdf file decoding the image
+/// bytes poppler produced, the deterministic re-encoding done by
+/// [render_page_bytes] is skipped as it doesn't change the pixels
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip(data, info, options))
+)]
+async fn render_page<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    page: u32,
+    options: &'options RenderOptions,
+) -> Result<image::DynamicImage> {
+    let output = render_page_poppler(data, info, page, options).await?;
+    let image = image::load_from_memory_with_format(&output, options.poppler_format())?;
 
-    Ok(value.into_owned())
+    Ok(postprocess_page(image, options))
 }
 
-/// Determines the executable path for the provided command
-pub fn get_executable_path(command: &str) -> String {
-    if let Ok(poppler_path) = std::env::var("PDF2IMAGE_POPPLER_PATH") {
-        #[cfg(target_os = "windows")]
-        return format!("{}\\{}.exe", poppler_path, command);
-        #[cfg(not(target_os = "windows"))]
-        return format!("{}/{}", poppler_path, command);
+/// Applies the in-crate post processing options to a decoded page
+fn postprocess_page(
+    mut image: image::DynamicImage,
+    options: &RenderOptions,
+) -> image::DynamicImage {
+    if let Some(background) = options.flatten_background {
+        if image.color().has_alpha() {
+            image = flatten_alpha(&image, background);
+        }
     }
 
-    #[cfg(target_os = "windows")]
-    return format!("{}.exe", command);
+    if let Some((width, height, filter)) = options.resize {
+        image = image.resize_exact(width, height, filter);
+    }
 
-    #[cfg(not(target_os = "windows"))]
-    return command.to_string();
+    if let Some((width, height)) = options.exact_size {
+        if image.dimensions() != (width, height) {
+            let background = options
+                .flatten_background
+                .unwrap_or(image::Rgba([255, 255, 255, 255]));
+            image = fit_exact(&image, width, height, background);
+        }
+    }
+
+    image
 }
 
-pub async fn extract_pdf_info(pdf: &[u8]) -> Result<(u32, bool)> {
-    let mut child = Command::new(get_executable_path("pdfinfo"))
-        .args(["-"])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()?;
+/// Pads or crops the image to exactly the width and height keeping it
+/// centered, padding is filled with the background color
+fn fit_exact(
+    image: &image::DynamicImage,
+    width: u32,
+    height: u32,
+    background: image::Rgba<u8>,
+) -> image::DynamicImage {
+    let mut canvas = image::RgbaImage::from_pixel(width, height, background);
+    let x = (i64::from(width) - i64::from(image.width())) / 2;
+    let y = (i64::from(height) - i64::from(image.height())) / 2;
+    image::imageops::overlay(&mut canvas, &image.to_rgba8(), x, y);
 
-    // UNWRAP SAFETY: The child process is guaranteed to have a stdin as .stdin(Stdio::piped()) was called
-    child.stdin.as_mut().unwrap().write_all(pdf).await?;
-    let output = child.wait_with_output().await?;
-    let mut splits = output.stdout.split(|&x| x == b'\n');
+    // Keep the color type poppler produced
+    let canvas = image::DynamicImage::ImageRgba8(canvas);
+    match image.color() {
+        image::ColorType::L8 => canvas.into_luma8().into(),
+        image::ColorType::La8 => canvas.into_luma_alpha8().into(),
+        image::ColorType::Rgb8 => canvas.into_rgb8().into(),
+        _ => canvas,
+    }
+}
 
-    let page_count: u32 = splits
-        .clone()
-        .find(|line| line.starts_with(b"Pages:"))
-        .map(|line| {
-            let line = std::str::from_utf8(line)?;
-            let pg_str = line
-                .split_whitespace()
-                .last()
-                .ok_or(PDF2ImageError::UnableToExtractPageCount)?;
-            pg_str
-                .parse::<u32>()
-                .map_err(|_| PDF2ImageError::UnableToExtractPageCount)
-        })
-        .ok_or(PDF2ImageError::UnableToExtractPageCount)??;
-
-    let encrypted = splits
-        .find(|line| line.starts_with(b"Encrypted:"))
-        .map(|line| {
-            let line = std::str::from_utf8(line)?;
-            Ok(
-                match line
-                    .split_whitespace()
-                    .last()
-                    .ok_or(PDF2ImageError::UnableToExtractEncryptionStatus)?
+/// Renders a specific page from the pdf file returning the encoded image bytes
+pub(crate) async fn render_page_bytes<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    page: u32,
+    options: &'options RenderOptions,
+) -> Result<Vec<u8>> {
+    let output = render_page_poppler(data, info, page, options).await?;
+
+    if options.output_format() == image::ImageFormat::WebP {
+        return encode_webp(&output);
+    }
+
+    if options.deterministic && options.output_format() == image::ImageFormat::Png {
+        return normalize_png(&output);
+    }
+
+    Ok(output)
+}
+
+/// Renders a specific page from the pdf file returning the encoded
+/// image bytes poppler produced
+async fn render_page_poppler<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    page: u32,
+    options: &'options RenderOptions,
+) -> Result<Vec<u8>> {
+    let adjusted = effective_options(info, page, options);
+    let options = adjusted.as_ref().unwrap_or(options);
+
+    check_max_pixels(info, page, options)?;
+
+    let backend = options.backend();
+    let result = render_page_backend(data, page, options, backend).await;
+
+    if backend != Backend::Pdftocairo || !options.fallback_to_pdftoppm || !is_not_found(&result) {
+        return result;
+    }
+
+    push_warnings(
+        options,
+        ["pdftocairo is not available, falling back to pdftoppm".to_string()],
+    );
+
+    let result = render_page_backend(data, page, options, Backend::Pdftoppm).await;
+
+    if is_not_found(&result) {
+        return Err(PDF2ImageError::NoRendererAvailable);
+    }
+
+    result
+}
+
+/// The options with the resolution adjusted for the page, `None` when
+/// no adjustment is needed
+///
+/// The resolution is multiplied by the [PdfInfo::user_unit] when
+/// [RenderOptions::respect_user_unit] is set and then lowered to fit the
+/// [RenderOptions::auto_downscale_to] pixel budget
+fn effective_options(info: &PdfInfo, page: u32, options: &RenderOptions) -> Option<RenderOptions> {
+    let mut adjusted = None;
+
+    if options.respect_user_unit && info.user_unit != 1.0 {
+        let unit = info.user_unit as f32;
+        let mut scaled = options.clone();
+        scaled.resolution = scale_resolution(&options.resolution, unit);
+        adjusted = Some(scaled);
+    }
+
+    // Scaling and cropping fix the output size regardless of the resolution
+    let budget = options.auto_downscale_to.filter(|_| {
+        options.scale.is_none() && options.scale_to.is_none() && options.crop.is_none()
+    });
+
+    if let (Some(budget), Some(page_size)) = (budget, info.page_size(page)) {
+        let current = adjusted.as_ref().unwrap_or(options);
+        let pixels = current.estimated_pixels(page_size);
+
+        if pixels > budget {
+            let mut downscaled = current.clone();
+            let factor = (budget as f64 / pixels as f64).sqrt() as f32;
+            downscaled.resolution = scale_resolution(&current.resolution, factor);
+
+            // Rounding each side up can still overshoot the budget slightly
+            for _ in 0..16 {
+                if downscaled.estimated_pixels(page_size) <= budget {
+                    break;
+                }
+                downscaled.resolution = scale_resolution(&downscaled.resolution, 0.99);
+            }
+
+            adjusted = Some(downscaled);
+        }
+    }
+
+    adjusted
+}
+
+/// Multiplies both axes of the resolution by the factor
+fn scale_resolution(resolution: &DPI, factor: f32) -> DPI {
+    match *resolution {
+        DPI::Uniform(dpi) => DPI::Uniform(dpi * factor),
+        DPI::XY(dpi_x, dpi_y) => DPI::XY(dpi_x * factor, dpi_y * factor),
+    }
+}
+
+/// Runs the checks done before spawning poppler for the page, for callers
+/// that may return a page without rendering it
+#[cfg(feature = "cache")]
+pub(crate) fn check_render_limits(
+    info: &PdfInfo,
+    page: u32,
+    options: &RenderOptions,
+) -> Result<()> {
+    let adjusted = effective_options(info, page, options);
+
+    check_max_pixels(info, page, adjusted.as_ref().unwrap_or(options))
+}
+
+/// Rejects the page when it would exceed the [RenderOptions::max_pixels] limit
+fn check_max_pixels(info: &PdfInfo, page: u32, options: &RenderOptions) -> Result<()> {
+    if let Some(limit) = options.max_pixels {
+        let pixels = info
+            .page_size(page)
+            .map(|page_size| options.estimated_pixels(page_size));
+
+        if let Some(pixels) = pixels.filter(|&pixels| pixels > limit) {
+            return Err(PDF2ImageError::OutputTooLarge { pixels, limit });
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the result failed because the executable could not be found
+fn is_not_found<T>(result: &Result<T>) -> bool {
+    matches!(result, Err(PDF2ImageError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound)
+}
+
+/// Renders a specific page from the pdf file using the provided backend
+async fn render_page_backend<'data, 'options: 'data>(
+    data: &'data [u8],
+    page: u32,
+    options: &'options RenderOptions,
+    backend: Backend,
+) -> Result<Vec<u8>> {
+    let command = render_page_command(page, options, backend).await?;
+
+    run_poppler(command, data, options).await
+}
+
+/// Creates the command rendering a specific page with the provided backend
+async fn render_page_command(
+    page: u32,
+    options: &RenderOptions,
+    backend: Backend,
+) -> Result<Command> {
+    let cli_options = options.to_cli_args(backend)?;
+
+    if !options.render_annotations && backend == Backend::Pdftoppm {
+        require_version(options, "render_annotations", HIDE_ANNOTATIONS_VERSION).await?;
+    }
+
+    let executable = get_executable_path(backend.executable(), options);
+
+    // pdftoppm writes netpbm output when no format is given
+    let format_arg = match (options.poppler_format(), backend) {
+        (image::ImageFormat::Png, _) => Some("-png"),
+        (image::ImageFormat::Pnm, Backend::Pdftoppm) => None,
+        (image::ImageFormat::Pnm, _) => {
+            return Err(PDF2ImageError::UnsupportedOption {
+                backend,
+                option: "OutputFormat::Pnm",
+            })
+        }
+        _ => Some("-jpeg"),
+    };
+
+    let poppler_args: &[&str] = if backend == Backend::Pdftocairo {
+        &["-", "-"]
+    } else {
+        &[]
+    };
+
+    let mut command = Command::new(&executable);
+    command
+        // Add the poppler args
+        .args(poppler_args)
+        .args(format_arg)
+        .arg("-singlefile")
+        // Add the page args
+        .args([
+            "-f".to_string(),
+            format!("{page}"),
+            "-l".to_string(),
+            format!("{page}"),
+        ])
+        // Add the cli options
+        .args(cli_options);
+
+    Ok(command)
+}
+
+/// Re-encodes a PNG through the image crate using fixed encoder settings,
+/// dropping any metadata chunks so identical pages produce identical bytes
+fn normalize_png(output: &[u8]) -> Result<Vec<u8>> {
+    let image = image::load_from_memory_with_format(output, image::ImageFormat::Png)?;
+
+    let mut normalized = Vec::new();
+    let encoder = image::codecs::png::PngEncoder::new_with_quality(
+        &mut normalized,
+        image::codecs::png::CompressionType::Default,
+        image::codecs::png::FilterType::Adaptive,
+    );
+    image.write_with_encoder(encoder)?;
+
+    Ok(normalized)
+}
+
+/// Re-encodes the PNG poppler produced as lossless WebP, lossy encoding
+/// isn't supported by the image crate
+fn encode_webp(output: &[u8]) -> Result<Vec<u8>> {
+    let image = image::load_from_memory_with_format(output, image::ImageFormat::Png)?;
+
+    let mut encoded = Vec::new();
+    let encoder = image::codecs::webp::WebPEncoder::new_lossless(&mut encoded);
+    image.write_with_encoder(encoder)?;
+
+    Ok(encoded)
+}
+
+/// Extracts the text contents of a pdf file from a single page
+pub async fn pdftext_single_page<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    page: u32,
+    options: &'options RenderOptions,
+) -> Result<String> {
+    if info.encrypted && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
+
+    let image = render_page_text(data, page, options).await?;
+
+    Ok(image)
+}
+
+/// Extracts the text contents of a pdf file from multiple pages,
+/// returning a separate string for each page
+///
+/// If you want all pages as a string it will likely be more performant
+/// to use [pdftext_all_pages]
+///
+/// Contiguous page selections are extracted with a single pdftotext
+/// process, other selections spawn one process per page
+pub async fn pdftext_multi_page<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    pages: Pages,
+    options: &'options RenderOptions,
+) -> Result<Vec<String>> {
+    let pages = pdftext_multi_page_numbered(data, info, pages, options).await?;
+
+    Ok(pages.into_iter().map(|(_, text)| text).collect())
+}
+
+/// Extracts the text contents of a pdf file from multiple pages,
+/// returning each page number along with its text
+///
+/// The page numbers are kept when [RenderOptions::skip_empty_pages]
+/// omits pages without any text
+pub async fn pdftext_multi_page_numbered<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    pages: Pages,
+    options: &'options RenderOptions,
+) -> Result<Vec<(u32, String)>> {
+    if info.encrypted && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
+
+    let pages_range = pages.resolve_with_options(info, options)?;
+
+    // Without page breaks the pages of a range can't be told apart
+    let bounds = contiguous_bounds(&pages_range).filter(|_| !options.no_page_breaks);
+
+    let texts: Vec<(u32, String)> = if let Some((first, last)) = bounds {
+        let text = render_page_range_text(data, first, last, options).await?;
+        split_page_texts(first..=last, &text)
+    } else {
+        pages_range
+            .into_iter()
+            .map(|page| -> BoxFuture<'data, Result<(u32, String)>> {
+                Box::pin(async move {
+                    render_page_text(data, page, options)
+                        .await
+                        .map(|text| (page, text))
+                        .map_err(|err| err.with_page(page))
+                })
+            })
+            .collect::<FuturesOrdered<BoxFuture<'data, Result<(u32, String)>>>>()
+            .try_collect()
+            .await?
+    };
+
+    if !options.skip_empty_pages {
+        return Ok(texts);
+    }
+
+    Ok(texts
+        .into_iter()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .collect())
+}
+
+/// Extracts the text contents of a pdf file from all pages as
+/// one big string, use [pdftext_multi_page] to get a separate
+/// string for each page
+pub async fn pdftext_all_pages<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    pages: Pages,
+    options: &'options RenderOptions,
+) -> Result<String> {
+    if info.encrypted && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
+
+    if let Pages::All = pages {
+        return render_all_pages_text(data, options).await;
+    }
+
+    let pages_range = pages.resolve_with_options(info, options)?;
+
+    if let Some((first, last)) = contiguous_bounds(&pages_range) {
+        return render_page_range_text(data, first, last, options).await;
+    }
+
+    pages_range
+        .into_iter()
+        .map(|page| -> BoxFuture<'data, Result<String>> {
+            Box::pin(async move {
+                render_page_text(data, page, options)
+                    .await
+                    .map_err(|err| err.with_page(page))
+            })
+        })
+        .collect::<FuturesOrdered<BoxFuture<'data, Result<String>>>>()
+        .try_collect()
+        .await
+}
+
+/// Extracts the text contents of all pages of a pdf file read from
+/// `reader`, streaming it into pdftotext without buffering the file
+///
+/// No [PdfInfo] is read first, so an encrypted file without a
+/// [RenderOptions::password] fails in pdftotext itself
+pub async fn pdftext_from_reader(
+    reader: impl AsyncRead + Unpin,
+    options: &RenderOptions,
+) -> Result<String> {
+    render_all_pages_text(reader, options).await
+}
+
+/// Renders a specific page from the pdf file
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip(data, options))
+)]
+async fn render_page_text<'data, 'options: 'data>(
+    data: &'data [u8],
+    page: u32,
+    options: &'options RenderOptions,
+) -> Result<String> {
+    render_page_range_text(data, page, page, options).await
+}
+
+/// Extracts the text of an inclusive page range with a single
+/// pdftotext process, pages are separated by form feeds
+async fn render_page_range_text<'data, 'options: 'data>(
+    data: &'data [u8],
+    first: u32,
+    last: u32,
+    options: &'options RenderOptions,
+) -> Result<String> {
+    let cli_options = options.to_cli_args(Backend::Pdftotext)?;
+
+    let mut command = Command::new(get_executable_path(
+        Backend::Pdftotext.executable(),
+        options,
+    ));
+    command
+        // Take input from stdin and provide to stdout
+        .args(["-", "-"])
+        // Add the page args
+        .args([
+            "-f".to_string(),
+            format!("{first}"),
+            "-l".to_string(),
+            format!("{last}"),
+        ])
+        // Add the cli options
+        .args(cli_options);
+
+    let output = run_poppler(command, data, options).await?;
+
+    decode_text(output, options)
+}
+/// Extracts the text of every page from the pdf file read from `input`
+async fn render_all_pages_text(
+    input: impl AsyncRead + Unpin,
+    options: &RenderOptions,
+) -> Result<String> {
+    let cli_options = options.to_cli_args(Backend::Pdftotext)?;
+
+    let mut command = Command::new(get_executable_path(
+        Backend::Pdftotext.executable(),
+        options,
+    ));
+    command
+        // Take input from stdin and provide to stdout
+        .args(["-", "-"])
+        // Add the cli options
+        .args(cli_options);
+
+    let output = run_poppler(command, input, options).await?;
+
+    decode_text(output, options)
+}
+
+/// Returns the first and last page when the pages form a single
+/// ascending run without gaps
+fn contiguous_bounds(pages: &[u32]) -> Option<(u32, u32)> {
+    let (&first, &last) = (pages.first()?, pages.last()?);
+
+    pages
+        .windows(2)
+        .all(|pair| pair[1] == pair[0] + 1)
+        .then_some((first, last))
+}
+
+/// Splits the text of a page range into the text of each page, pdftotext
+/// ends every page with a form feed which is kept with the page
+fn split_page_texts(pages: std::ops::RangeInclusive<u32>, text: &str) -> Vec<(u32, String)> {
+    pages
+        .zip(text.split_inclusive('\x0C').map(str::to_string))
+        .collect()
+}
+
+/// Composites the image over the background color using source-over
+/// blending, dropping the alpha channel from the result
+fn flatten_alpha(image: &image::DynamicImage, background: image::Rgba<u8>) -> image::DynamicImage {
+    let image = image.to_rgba8();
+    let [bg_r, bg_g, bg_b, bg_a] = background.0.map(|value| f32::from(value) / 255.0);
+
+    let flattened = image::RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image
+            .get_pixel(x, y)
+            .0
+            .map(|value| f32::from(value) / 255.0);
+
+        let out_a = a + bg_a * (1.0 - a);
+        let blend = |src: f32, dst: f32| {
+            if out_a <= 0.0 {
+                return 0;
+            }
+            let value = (src * a + dst * bg_a * (1.0 - a)) / out_a;
+            (value * 255.0).round().clamp(0.0, 255.0) as u8
+        };
+
+        image::Rgb([blend(r, bg_r), blend(g, bg_g), blend(b, bg_b)])
+    });
+
+    image::DynamicImage::ImageRgb8(flattened)
+}
+
+/// Extracts the text contents of all pages of a pdf file as a stream,
+/// yielding the text of each page as pdftotext produces it instead of
+/// buffering the whole document
+///
+/// Pages are split on the form feed page separator which is kept at the
+/// end of each page, matching the output of the other text functions
+/// (with [RenderOptions::no_page_breaks] the whole text is a single item)
+pub async fn pdftext_stream<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    options: &'options RenderOptions,
+) -> Result<impl Stream<Item = Result<String>> + 'data> {
+    if info.encrypted && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
+
+    let cli_options = options.to_cli_args(Backend::Pdftotext)?;
+
+    let mut command = Command::new(get_executable_path(
+        Backend::Pdftotext.executable(),
+        options,
+    ));
+    command
+        // Take input from stdin and provide to stdout
+        .args(["-", "-"])
+        // Add the cli options
+        .args(cli_options);
+
+    #[cfg(feature = "tracing")]
+    let started = std::time::Instant::now();
+
+    let permit = acquire_process_slot().await;
+    let mut child = spawn_poppler(&mut command, options, false)?;
+
+    // UNWRAP SAFETY: The child process is guaranteed to have a stdin and stdout as they were piped
+    let mut stdin = child.stdin.take().unwrap();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    let stderr = child.stderr.take();
+
+    // The input is written while the pages are read to avoid filling the pipe buffers
+    let write_input = futures::stream::once(async move {
+        stdin.write_all(data).await?;
+        stdin.shutdown().await?;
+        drop(stdin);
+        std::io::Result::Ok(())
+    })
+    .filter_map(|result| async move { result.err().map(|err| Err(err.into())) });
+
+    let read_warnings = futures::stream::once(async move {
+        let mut output = Vec::new();
+        if let Some(mut stderr) = stderr {
+            if stderr.read_to_end(&mut output).await.is_ok() {
+                push_stderr_warnings(options, &output);
+            }
+        }
+    })
+    .filter_map(|_| async { None });
+
+    // The process slot is held by the stream until the child exits
+    let read_pages = futures::stream::try_unfold(
+        (stdout, child, permit),
+        move |(mut stdout, mut child, permit)| async move {
+            let mut page = Vec::new();
+
+            if stdout.read_until(b'\x0C', &mut page).await? == 0 {
+                let _status = child.wait().await?;
+
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    program = ?Backend::Pdftotext.executable(),
+                    status = %_status,
+                    duration = ?started.elapsed(),
+                    "poppler command finished"
+                );
+
+                return Ok(None);
+            }
+
+            let text = decode_text(page, options)?;
+            Ok(Some((text, (stdout, child, permit))))
+        },
+    );
+
+    Ok(futures::stream::select(
+        futures::stream::select(write_input, read_warnings),
+        read_pages,
+    ))
+}
+
+/// Exports a single page of the pdf file as Encapsulated PostScript (EPS)
+///
+/// Requires the pdftocairo backend to be enabled through [RenderOptions::pdftocairo],
+/// the raster only greyscale, mono, scale and scale_to options are rejected
+pub async fn render_page_eps<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    page: u32,
+    options: &'options RenderOptions,
+) -> Result<Vec<u8>> {
+    if info.encrypted && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
+
+    render_postscript(data, "-eps", Some((page, page)), options).await
+}
+
+/// Exports the pdf file pages as a single PostScript document, the selected
+/// pages must form a contiguous ascending range
+///
+/// Requires the pdftocairo backend to be enabled through [RenderOptions::pdftocairo],
+/// the raster only greyscale, mono, scale and scale_to options are rejected
+pub async fn render_pdf_ps<'data, 'options: 'data>(
+    data: &'data [u8],
+    info: &'options PdfInfo,
+    pages: Pages,
+    options: &'options RenderOptions,
+) -> Result<Vec<u8>> {
+    if info.encrypted && options.password.is_none() {
+        return Err(PDF2ImageError::NoPasswordForEncryptedPDF);
+    }
+
+    if let Pages::All = pages {
+        return render_postscript(data, "-ps", None, options).await;
+    }
+
+    let pages_range = pages.resolve_with_options(info, options)?;
+
+    if pages_range.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let Some((first, last)) = contiguous_bounds(&pages_range) else {
+        return Err(PDF2ImageError::NonContiguousPages);
+    };
+
+    render_postscript(data, "-ps", Some((first, last)), options).await
+}
+
+/// Exports the pdf file using one of the pdftocairo PostScript
+/// output formats (-ps or -eps) optionally limited to a page range
+async fn render_postscript<'data, 'options: 'data>(
+    data: &'data [u8],
+    format: &str,
+    pages: Option<(u32, u32)>,
+    options: &'options RenderOptions,
+) -> Result<Vec<u8>> {
+    if !options.pdftocairo {
+        return Err(PDF2ImageError::PdftocairoRequired);
+    }
+
+    let cli_options = postscript_args(options)?;
+
+    let mut command = Command::new(get_executable_path(
+        Backend::Pdftocairo.executable(),
+        options,
+    ));
+    command
+        // Take input from stdin and provide to stdout
+        .args([format, "-", "-"])
+        // Add the cli options
+        .args(cli_options);
+
+    if let Some((first, last)) = pages {
+        // Add the page args
+        command.args([
+            "-f".to_string(),
+            format!("{first}"),
+            "-l".to_string(),
+            format!("{last}"),
+        ]);
+    }
+
+    run_poppler(command, data, options).await
+}
+
+/// The pdftocairo cli arguments for the PostScript outputs, the raster
+/// only options are rejected rather than passed to -ps/-eps
+fn postscript_args(options: &RenderOptions) -> Result<Vec<String>> {
+    let unsupported = [
+        (options.greyscale, "greyscale with PostScript output"),
+        (options.mono, "mono with PostScript output"),
+        (options.scale.is_some(), "scale with PostScript output"),
+        (
+            options.scale_to.is_some(),
+            "scale_to with PostScript output",
+        ),
+    ];
+
+    if let Some((_, option)) = unsupported.into_iter().find(|(set, _)| *set) {
+        return Err(PDF2ImageError::UnsupportedOption {
+            backend: Backend::Pdftocairo,
+            option,
+        });
+    }
+
+    options.to_cli_args(Backend::Pdftocairo)
+}
+
+/// Decodes the text output from pdftotext using the text encoding of the
+/// options, normalizing it when [RenderOptions::normalize_text] is set
+fn decode_text(output: Vec<u8>, options: &RenderOptions) -> Result<String> {
+    let text = match options.text_encoding {
+        TextEncoding::Lossy => String::from_utf8_lossy(&output).into_owned(),
+        TextEncoding::Strict => String::from_utf8(output).map_err(PDF2ImageError::InvalidUtf8)?,
+    };
+
+    if options.normalize_text {
+        Ok(normalize_text(&text))
+    } else {
+        Ok(text)
+    }
+}
+
+/// Strips a leading byte order mark and converts `\r\n` and `\r` line
+/// endings to `\n`
+fn normalize_text(text: &str) -> String {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Spawns the provided poppler command writing the pdf `input` to its stdin
+/// and returning its stdout, anything the command writes to stderr is
+/// collected into the [RenderOptions::warnings_sink] when one is set
+pub(crate) async fn run_poppler(
+    command: Command,
+    input: impl AsyncRead + Unpin,
+    options: &RenderOptions,
+) -> Result<Vec<u8>> {
+    let output = run_poppler_output(command, input, options, false).await?;
+
+    push_stderr_warnings(options, &output.stderr);
+
+    Ok(output.stdout)
+}
+
+/// Like [run_poppler] but fails with [PDF2ImageError::CommandFailed] when
+/// the command exits unsuccessfully, for commands that write their output
+/// to files where a failure would otherwise go unnoticed
+pub(crate) async fn run_poppler_checked(
+    command: Command,
+    input: impl AsyncRead + Unpin,
+    options: &RenderOptions,
+    executable: &'static str,
+) -> Result<Vec<u8>> {
+    let output = run_poppler_output(command, input, options, true).await?;
+
+    push_stderr_warnings(options, &output.stderr);
+
+    if !output.status.success() {
+        return Err(PDF2ImageError::CommandFailed {
+            executable,
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(output.stdout)
+}
+
+/// Runs the poppler command returning its raw output, stderr is captured
+/// when `capture_stderr` is set or there is a warnings sink
+pub(crate) async fn run_poppler_output(
+    mut command: Command,
+    mut input: impl AsyncRead + Unpin,
+    options: &RenderOptions,
+    capture_stderr: bool,
+) -> Result<std::process::Output> {
+    #[cfg(feature = "tracing")]
+    let started = std::time::Instant::now();
+
+    let _permit = acquire_process_slot().await;
+    let mut child = spawn_poppler(&mut command, options, capture_stderr)?;
+
+    // UNWRAP SAFETY: The child process is guaranteed to have a stdin and stdout as they were piped
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take();
+
+    // The input must be written while the output is read, writing all the
+    // input first deadlocks when the child fills the stdout pipe buffer
+    let write_input = async move {
+        tokio::io::copy(&mut input, &mut stdin).await?;
+        // Close stdin so the child sees EOF as soon as the input is written
+        stdin.shutdown().await?;
+        drop(stdin);
+        Ok(())
+    };
+    let read_output = async move {
+        let mut output = Vec::new();
+        copy_limited(&mut stdout, &mut output, options.max_output_bytes).await?;
+        Ok(output)
+    };
+    // An exceeded output limit stops the other futures, dropping the child kills it
+    let (_, stdout, stderr) = futures::try_join!(write_input, read_output, read_stderr(stderr))?;
+    let output = std::process::Output {
+        status: child.wait().await?,
+        stdout,
+        stderr,
+    };
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        program = ?command.as_std().get_program(),
+        status = %output.status,
+        duration = ?started.elapsed(),
+        "poppler command finished"
+    );
+
+    Ok(output)
+}
+
+/// Runs the poppler command copying its stdout into the `writer` as it is
+/// produced rather than collecting it
+async fn run_poppler_to_writer<W>(
+    mut command: Command,
+    data: &[u8],
+    options: &RenderOptions,
+    writer: &mut W,
+) -> Result<u64>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    #[cfg(feature = "tracing")]
+    let started = std::time::Instant::now();
+
+    let _permit = acquire_process_slot().await;
+    let mut child = spawn_poppler(&mut command, options, false)?;
+
+    // UNWRAP SAFETY: The child process is guaranteed to have a stdin and stdout as they were piped
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take();
+
+    let write_input = async move {
+        stdin.write_all(data).await?;
+        stdin.shutdown().await?;
+        drop(stdin);
+        Ok(())
+    };
+    let copy_output = copy_limited(&mut stdout, writer, options.max_output_bytes);
+    // A failed writer or exceeded output limit stops the other futures,
+    // dropping the child kills it
+    let (_, copied, warnings) = futures::try_join!(write_input, copy_output, read_stderr(stderr))?;
+    let _status = child.wait().await?;
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        program = ?command.as_std().get_program(),
+        status = %_status,
+        duration = ?started.elapsed(),
+        "poppler command finished"
+    );
+
+    push_stderr_warnings(options, &warnings);
+
+    Ok(copied)
+}
+
+/// Copies the `reader` into the `writer`, failing with
+/// [PDF2ImageError::OutputLimitExceeded] once more than `limit` bytes are read
+async fn copy_limited<R, W>(reader: &mut R, writer: &mut W, limit: Option<u64>) -> Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let Some(limit) = limit else {
+        return Ok(tokio::io::copy(reader, writer).await?);
+    };
+
+    // Reading one byte past the limit tells an exceeded limit apart from an exact fit
+    let copied = tokio::io::copy(&mut reader.take(limit.saturating_add(1)), writer).await?;
+    if copied > limit {
+        return Err(PDF2ImageError::OutputLimitExceeded { limit });
+    }
+
+    Ok(copied)
+}
+
+/// Reads the piped stderr of a child, empty when stderr was not piped
+async fn read_stderr(stderr: Option<ChildStderr>) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    if let Some(mut stderr) = stderr {
+        stderr.read_to_end(&mut output).await?;
+    }
+
+    Ok(output)
+}
+
+/// Spawns the provided poppler command with piped stdin and stdout, stderr
+/// is only piped when requested or there is a [RenderOptions::warnings_sink]
+/// to collect it
+///
+/// The child is killed when dropped so cancelled renders don't leave
+/// orphaned poppler processes behind
+fn spawn_poppler(
+    command: &mut Command,
+    options: &RenderOptions,
+    capture_stderr: bool,
+) -> Result<Child> {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        program = ?command.as_std().get_program(),
+        args = ?redacted_args(command),
+        "spawning poppler command"
+    );
+
+    let stderr = match (capture_stderr, &options.warnings_sink) {
+        (false, None) => Stdio::inherit(),
+        _ => Stdio::piped(),
+    };
+
+    let child = command
+        .envs(options.env.iter().map(|(key, value)| (key, value)))
+        // Kill the child if the future driving it is dropped before completion
+        .kill_on_drop(true)
+        // Pipe input and output for use
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(stderr)
+        .spawn()?;
+
+    Ok(child)
+}
+
+/// The command arguments with any password values replaced
+#[cfg(feature = "tracing")]
+fn redacted_args(command: &Command) -> Vec<String> {
+    let mut redact_next = false;
+
+    command
+        .as_std()
+        .get_args()
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if std::mem::take(&mut redact_next) {
+                return "<redacted>".to_string();
+            }
+            redact_next = arg == "-upw" || arg == "-opw";
+            arg.into_owned()
+        })
+        .collect()
+}
+
+/// Adds each non empty line of the stderr output to the [RenderOptions::warnings_sink]
+fn push_stderr_warnings(options: &RenderOptions, stderr: &[u8]) {
+    if options.warnings_sink.is_none() {
+        return;
+    }
+
+    let stderr = String::from_utf8_lossy(stderr);
+    let warnings = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string);
+
+    push_warnings(options, warnings);
+}
+
+/// Adds the warnings to the [RenderOptions::warnings_sink] if one is set
+fn push_warnings(options: &RenderOptions, warnings: impl IntoIterator<Item = String>) {
+    if let Some(warnings_sink) = &options.warnings_sink {
+        // Poisoning only occurs if another user of the sink panicked, the
+        // collected warnings are still usable so the poison is ignored
+        let mut sink = warnings_sink
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        sink.extend(warnings);
+    }
+}
+
+/// The poppler executables used by this crate
+pub(crate) const POPPLER_BINARIES: &[&str] = &[
+    "pdftoppm",
+    "pdftocairo",
+    "pdftotext",
+    "pdfinfo",
+    "pdfimages",
+    "pdffonts",
+    "pdftohtml",
+    "pdfseparate",
+    "pdfunite",
+];
+
+/// Determines the executable path for the provided command, using
+/// the [RenderOptions::binary_overrides] entry when one is present
+pub fn get_executable_path(command: &str, options: &RenderOptions) -> String {
+    if let Some(executable) = options.binary_overrides.get(command) {
+        return executable.clone();
+    }
+
+    if let Ok(poppler_path) = std::env::var("PDF2IMAGE_POPPLER_PATH") {
+        #[cfg(target_os = "windows")]
+        return format!("{}\\{}.exe", poppler_path, command);
+        #[cfg(not(target_os = "windows"))]
+        return format!("{}/{}", poppler_path, command);
+    }
+
+    #[cfg(target_os = "windows")]
+    return format!("{}.exe", command);
+
+    #[cfg(not(target_os = "windows"))]
+    return command.to_string();
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+pub async fn extract_pdf_info(pdf: &[u8], options: &RenderOptions) -> Result<PdfInfo> {
+    let mut command = Command::new(get_executable_path("pdfinfo", options));
+    command
+        // Avoid locale specific number formatting in the output
+        .env("LC_ALL", "C")
+        // Report the size and boxes of every page, pdfinfo clamps the last
+        // page to the page count
+        .args(["-box", "-f", "1", "-l", &i32::MAX.to_string()])
+        .args(["-"]);
+
+    if let Some(password) = &options.password {
+        command.args(password.cli_args());
+    }
+
+    // stderr is always captured so reconstructed (damaged) files can be flagged
+    let output = run_poppler_output(command, pdf, options, true).await?;
+    push_stderr_warnings(options, &output.stderr);
+
+    parse_pdf_info(&output.stdout, &output.stderr, pdf)
+}
+
+/// Parses the pdfinfo output for the `pdf`, the raw file bytes are
+/// scanned for the names pdfinfo doesn't report
+fn parse_pdf_info(stdout: &[u8], stderr: &[u8], pdf: &[u8]) -> Result<PdfInfo> {
+    let damaged = String::from_utf8_lossy(stderr).contains("PDF file is damaged");
+    let splits = stdout.split(|&x| x == b'\n');
+
+    // Document level "Key: Value" lines, the per page lines are parsed below
+    let raw_fields: HashMap<String, String> = splits
+        .clone()
+        .filter(|line| !is_page_line(line))
+        .filter_map(|line| {
+            let line = String::from_utf8_lossy(line);
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .filter(|(key, _)| !key.is_empty())
+        .collect();
+    let field = |key: &str| raw_fields.get(key).map(String::as_str);
+
+    let page_count = field("Pages")
+        .and_then(parse_grouped_number)
+        .ok_or(PDF2ImageError::UnableToExtractPageCount)?;
+
+    // Encrypted files list their permissions after the status,
+    // "Encrypted: yes (print:yes copy:no ...)"
+    let encrypted = match field("Encrypted").and_then(|value| value.split_whitespace().next()) {
+        Some("yes") => true,
+        Some("no") => false,
+        _ => return Err(PDF2ImageError::UnableToExtractEncryptionStatus),
+    };
+
+    let linearized = match field("Optimized") {
+        Some("yes") => true,
+        Some("no") => false,
+        _ => return Err(PDF2ImageError::UnableToExtractLinearizationStatus),
+    };
+
+    let pdf_version = field("PDF version")
+        .and_then(|value| {
+            let (major, minor) = value.split_once('.')?;
+            Some((major.parse::<u8>().ok()?, minor.parse::<u8>().ok()?))
+        })
+        .unwrap_or((1, 0));
+
+    let tagged = field("Tagged") == Some("yes");
+
+    let metadata = |key: &str| {
+        field(key)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let producer = metadata("Producer");
+    let creator = metadata("Creator");
+    let raw_names = scan_raw_names(pdf);
+    let user_unit = field("UserUnit")
+        .and_then(|value| value.parse::<f64>().ok())
+        .or(raw_names.user_unit)
+        .filter(|unit| unit.is_finite() && *unit > 0.0)
+        .unwrap_or(1.0);
+
+    let mut crop_sizes = Vec::new();
+    let mut media_sizes = Vec::new();
+    let mut rotations = Vec::new();
+
+    // Pages are reported as "Page    1 size: 612 x 792 pts (letter)" (the
+    // CropBox size) and "Page    1 rot:  0", followed by the boxes of every
+    // page as "Page    1 MediaBox:     0.00     0.00   612.00   792.00"
+    for line in splits.filter(|line| is_page_line(line)) {
+        let line = std::str::from_utf8(line)?;
+        let mut values = line.split_whitespace().skip(2);
+
+        match values.next() {
+            Some("size:") => {
+                let width = values.next().and_then(|value| value.parse::<f64>().ok());
+                let height = values.nth(1).and_then(|value| value.parse::<f64>().ok());
+                if let (Some(width), Some(height)) = (width, height) {
+                    crop_sizes.push((width, height));
+                }
+            }
+            Some("rot:") => {
+                rotations.push(values.next().and_then(|value| value.parse::<i32>().ok()));
+            }
+            Some("MediaBox:") => {
+                let mut values = values.map(str::parse::<f64>);
+                if let (Some(Ok(x1)), Some(Ok(y1)), Some(Ok(x2)), Some(Ok(y2))) =
+                    (values.next(), values.next(), values.next(), values.next())
                 {
-                    "yes" => true,
-                    "no" => false,
-                    _ => return Err(PDF2ImageError::UnableToExtractEncryptionStatus),
-                },
-            )
+                    media_sizes.push(((x2 - x1).abs(), (y2 - y1).abs()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Both renderers draw the MediaBox unless a crop is set, the CropBox
+    // size is only used when pdfinfo didn't report the boxes
+    let page_sizes = crop_sizes
+        .iter()
+        .enumerate()
+        .map(|(index, &crop_size)| {
+            let (width, height) = media_sizes.get(index).copied().unwrap_or(crop_size);
+            match rotations.get(index) {
+                Some(Some(90 | 270 | -90)) => (height, width),
+                _ => (width, height),
+            }
         })
-        .ok_or(PDF2ImageError::UnableToExtractEncryptionStatus)??;
+        .collect();
+
+    Ok(PdfInfo {
+        page_count,
+        encrypted,
+        linearized,
+        pdf_version,
+        tagged,
+        page_sizes,
+        producer,
+        creator,
+        damaged,
+        javascript: raw_names.javascript,
+        acroform: raw_names.acroform,
+        user_unit,
+        raw_fields,
+    })
+}
+
+/// Whether the pdfinfo line describes a single page, "Page    1 size: ..."
+fn is_page_line(line: &[u8]) -> bool {
+    line.strip_prefix(b"Page ").is_some_and(|rest| {
+        rest.trim_ascii_start()
+            .first()
+            .is_some_and(u8::is_ascii_digit)
+    })
+}
+
+/// Parses an integer that may contain digit grouping separators such as
+/// "1,024" or "1 024", returning `None` for non-numeric values
+fn parse_grouped_number(value: &str) -> Option<u32> {
+    let digits: String = value
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ',' | '.' | '\'' | ' ' | '\u{a0}' | '\u{202f}'))
+        .collect();
+
+    digits.parse().ok()
+}
+
+/// The names found by [scan_raw_names]
+#[derive(Debug, Default, PartialEq)]
+struct RawNames {
+    /// Whether a /JavaScript or /JS name is present
+    javascript: bool,
+    /// Whether an /AcroForm name is present
+    acroform: bool,
+    /// The value of the first /UserUnit entry
+    user_unit: Option<f64>,
+}
+
+/// Scans the raw PDF bytes once for the names pdfinfo doesn't report, names
+/// must be followed by a delimiter so `/JS` doesn't match `/JSON`
+///
+/// Entries inside compressed object streams are not found
+fn scan_raw_names(data: &[u8]) -> RawNames {
+    const LONGEST_NAME: usize = b"JavaScript".len();
+
+    let is_delimiter = |byte: u8| byte.is_ascii_whitespace() || b"()<>[]{}/%".contains(&byte);
+    let mut names = RawNames::default();
+
+    for (index, _) in data.iter().enumerate().filter(|(_, &byte)| byte == b'/') {
+        let rest = &data[index + 1..];
+
+        // Only the start of each name is checked to keep the scan linear
+        let window = &rest[..rest.len().min(LONGEST_NAME + 1)];
+        let Some(end) = window
+            .iter()
+            .position(|&byte| is_delimiter(byte))
+            .or((window.len() == rest.len()).then_some(rest.len()))
+        else {
+            continue;
+        };
+
+        match &rest[..end] {
+            b"JavaScript" | b"JS" => names.javascript = true,
+            b"AcroForm" => names.acroform = true,
+            b"UserUnit" if names.user_unit.is_none() => {
+                let value: String = rest[end..]
+                    .iter()
+                    .skip_while(|byte| byte.is_ascii_whitespace())
+                    .take_while(|byte| byte.is_ascii_digit() || **byte == b'.')
+                    .map(|&byte| char::from(byte))
+                    .collect();
+                names.user_unit = value.parse().ok();
+            }
+            _ => {}
+        }
+
+        if names.javascript && names.acroform && names.user_unit.is_some() {
+            break;
+        }
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_options::{RenderOptionsBuilder, ScaleTo};
+
+    #[test]
+    fn all_pages() {
+        assert_eq!(Pages::All.resolve(&PdfInfo::with_page_count(3)), [1, 2, 3]);
+        assert!(Pages::All.resolve(&PdfInfo::with_page_count(0)).is_empty());
+    }
+
+    #[test]
+    fn range_is_clamped_to_one_based_pages() {
+        let info = PdfInfo::with_page_count(3);
+
+        assert_eq!(Pages::Range(0..=2).resolve(&info), [1, 2]);
+        assert_eq!(Pages::Range(2..=10).resolve(&info), [2, 3]);
+        assert!(Pages::Range(4..=5).resolve(&info).is_empty());
+    }
+
+    #[test]
+    fn specific_pages_keep_order_and_drop_invalid() {
+        let info = PdfInfo::with_page_count(3);
+
+        assert_eq!(
+            Pages::Specific(vec![3, 0, 1, 4, 3]).resolve(&info),
+            [3, 1, 3]
+        );
+    }
 
-    Ok((page_count, encrypted))
+    #[test]
+    fn last_pages() {
+        let info = PdfInfo::with_page_count(5);
+
+        assert_eq!(Pages::Last(2).resolve(&info), [4, 5]);
+        assert!(Pages::Last(0).resolve(&info).is_empty());
+        assert_eq!(Pages::Last(5).resolve(&info), [1, 2, 3, 4, 5]);
+        assert_eq!(Pages::Last(u32::MAX).resolve(&info), [1, 2, 3, 4, 5]);
+        assert!(Pages::Last(3)
+            .resolve(&PdfInfo::with_page_count(0))
+            .is_empty());
+    }
+
+    #[test]
+    fn strict_pages_reject_what_lenient_pages_filter() {
+        let info = PdfInfo::with_page_count(10);
+        let pages = Pages::Range(1..=500);
+        let strict = RenderOptions {
+            strict_pages: true,
+            ..RenderOptions::default()
+        };
+
+        assert_eq!(
+            pages
+                .resolve_with_options(&info, &RenderOptions::default())
+                .unwrap(),
+            (1..=10).collect::<Vec<_>>()
+        );
+        assert!(matches!(
+            pages.resolve_with_options(&info, &strict),
+            Err(PDF2ImageError::PageOutOfRange {
+                page: 11,
+                page_count: 10,
+            })
+        ));
+        assert!(matches!(
+            Pages::Specific(vec![2, 0]).resolve_strict(&info),
+            Err(PDF2ImageError::PageOutOfRange { page: 0, .. })
+        ));
+        assert_eq!(Pages::Last(20).resolve_strict(&info).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn odd_and_even_pages() {
+        let info = PdfInfo::with_page_count(5);
+
+        assert_eq!(Pages::Odd.resolve(&info), [1, 3, 5]);
+        assert_eq!(Pages::Even.resolve(&info), [2, 4]);
+    }
+
+    #[test]
+    fn odd_and_even_pages_of_empty_pdf() {
+        let info = PdfInfo::with_page_count(0);
+
+        assert!(Pages::Odd.resolve(&info).is_empty());
+        assert!(Pages::Even.resolve(&info).is_empty());
+    }
+
+    #[test]
+    fn postscript_args_reject_raster_options() {
+        let greyscale = RenderOptionsBuilder::default()
+            .pdftocairo(true)
+            .greyscale(true)
+            .build()
+            .unwrap();
+        let scale_to = RenderOptionsBuilder::default()
+            .pdftocairo(true)
+            .scale_to(ScaleTo::width(100))
+            .build()
+            .unwrap();
+
+        for (options, expected) in [
+            (greyscale, "greyscale with PostScript output"),
+            (scale_to, "scale_to with PostScript output"),
+        ] {
+            assert!(matches!(
+                postscript_args(&options),
+                Err(PDF2ImageError::UnsupportedOption {
+                    backend: Backend::Pdftocairo,
+                    option,
+                }) if option == expected
+            ));
+        }
+    }
+
+    #[test]
+    fn postscript_args_keep_resolution() {
+        let options = RenderOptionsBuilder::default()
+            .pdftocairo(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(postscript_args(&options).unwrap(), ["-r", "150"]);
+    }
+
+    #[test]
+    fn flatten_alpha_composites_over_background() {
+        let image = image::RgbaImage::from_fn(3, 1, |x, _| match x {
+            0 => image::Rgba([0, 0, 0, 0]),
+            1 => image::Rgba([0, 0, 255, 255]),
+            _ => image::Rgba([0, 0, 0, 128]),
+        });
+        let background = image::Rgba([255, 255, 255, 255]);
+
+        let flattened = flatten_alpha(&image::DynamicImage::ImageRgba8(image), background);
+
+        let flattened = flattened.as_rgb8().expect("flattened to RGB8");
+        assert_eq!(flattened.get_pixel(0, 0).0, [255, 255, 255]);
+        assert_eq!(flattened.get_pixel(1, 0).0, [0, 0, 255]);
+        assert_eq!(flattened.get_pixel(2, 0).0, [127, 127, 127]);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn redacted_args_hide_passwords() {
+        let mut command = Command::new("pdftoppm");
+        command.args(["-r", "150", "-upw", "user", "-opw", "owner", "-"]);
+
+        assert_eq!(
+            redacted_args(&command),
+            ["-r", "150", "-upw", "<redacted>", "-opw", "<redacted>", "-"]
+        );
+    }
+
+    #[test]
+    fn contiguous_bounds_of_runs() {
+        assert_eq!(contiguous_bounds(&[3, 4, 5]), Some((3, 5)));
+        assert_eq!(contiguous_bounds(&[7]), Some((7, 7)));
+        assert_eq!(contiguous_bounds(&[1, 3]), None);
+        assert_eq!(contiguous_bounds(&[2, 1]), None);
+        assert_eq!(contiguous_bounds(&[]), None);
+    }
+
+    #[test]
+    fn split_page_texts_matches_per_page_output() {
+        let per_page = ["first page\n\x0C", "\x0C", "third page\n\x0C"];
+
+        assert_eq!(
+            split_page_texts(4..=6, &per_page.concat()),
+            [
+                (4, per_page[0].to_string()),
+                (5, per_page[1].to_string()),
+                (6, per_page[2].to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn scanner_producers_match_whole_words() {
+        let info = |producer: &str| PdfInfo {
+            producer: Some(producer.to_string()),
+            ..PdfInfo::with_page_count(1)
+        };
+
+        assert!(info("Canon iR-ADV C5535 PDF").has_scanner_producer());
+        assert!(info("HP Scan").has_scanner_producer());
+        assert!(info("ScanSnap Manager #S1500").has_scanner_producer());
+        assert!(!info("Canonical LibreOffice 7.3").has_scanner_producer());
+        assert!(!info("Microsoft Word").has_scanner_producer());
+        assert!(!PdfInfo::with_page_count(1).has_scanner_producer());
+    }
+
+    fn parse(stdout: &str) -> Result<PdfInfo> {
+        parse_pdf_info(stdout.as_bytes(), b"", b"%PDF-1.7")
+    }
+
+    const PDFINFO_OUTPUT: &str = "\
+Producer:       Canon iR-ADV C5535
+Tagged:         yes
+Pages:          2
+Encrypted:      no
+Page    1 size: 595 x 842 pts (A4)
+Page    1 rot:  0
+Page    2 size: 595 x 842 pts (A4)
+Page    2 rot:  90
+Page    1 MediaBox:     0.00     0.00   612.00   792.00
+Page    1 CropBox:      0.00     0.00   595.00   842.00
+Page    2 MediaBox:     0.00     0.00   612.00   792.00
+Page    2 CropBox:      0.00     0.00   595.00   842.00
+File size:      1234 bytes
+Optimized:      yes
+PDF version:    1.6
+";
+
+    #[test]
+    fn parse_pdf_info_fields() {
+        let info = parse(PDFINFO_OUTPUT).unwrap();
+
+        assert_eq!(info.page_count(), 2);
+        assert!(!info.is_encrypted());
+        assert!(info.is_linearized());
+        assert!(info.is_tagged());
+        assert_eq!(info.pdf_version(), (1, 6));
+        assert_eq!(info.producer(), Some("Canon iR-ADV C5535"));
+        assert_eq!(info.creator(), None);
+        assert_eq!(info.raw_fields()["Pages"], "2");
+        assert_eq!(info.raw_fields()["Encrypted"], "no");
+        assert_eq!(info.raw_fields()["File size"], "1234 bytes");
+        assert!(!info.raw_fields().contains_key("Page    1 size"));
+    }
+
+    #[test]
+    fn parse_pdf_info_media_box_sizes_with_rotation() {
+        let info = parse(PDFINFO_OUTPUT).unwrap();
+
+        assert_eq!(info.page_size(1), Some((612.0, 792.0)));
+        assert_eq!(info.page_size(2), Some((792.0, 612.0)));
+        assert_eq!(info.page_size(3), None);
+    }
+
+    #[test]
+    fn parse_pdf_info_crop_box_size_without_boxes() {
+        let info = parse(
+            "Pages: 1\nEncrypted: no\nOptimized: no\nPage    1 size: 595 x 842 pts\nPage    1 rot:  270\n",
+        )
+        .unwrap();
+
+        assert_eq!(info.page_size(1), Some((842.0, 595.0)));
+    }
+
+    #[test]
+    fn parse_pdf_info_grouped_page_counts() {
+        for pages in ["1,024", "1\u{a0}024", "1 024", "1.024"] {
+            let info = parse(&format!("Pages: {pages}\nEncrypted: no\nOptimized: no\n")).unwrap();
+
+            assert_eq!(info.page_count(), 1024);
+        }
+
+        assert!(matches!(
+            parse("Pages: many\nEncrypted: no\nOptimized: no\n"),
+            Err(PDF2ImageError::UnableToExtractPageCount)
+        ));
+    }
+
+    #[test]
+    fn parse_pdf_info_encrypted_with_permissions() {
+        let info = parse(
+            "Pages: 1\nEncrypted: yes (print:yes copy:no change:no addNotes:no algorithm:AES-256)\nOptimized: no\n",
+        )
+        .unwrap();
+
+        assert!(info.is_encrypted());
+    }
+
+    #[test]
+    fn parse_pdf_info_defaults() {
+        let info = parse("Pages: 1\nEncrypted: no\nOptimized: no\n").unwrap();
+
+        assert!(!info.is_linearized());
+        assert!(!info.is_tagged());
+        assert_eq!(info.pdf_version(), (1, 0));
+        assert_eq!(info.user_unit(), 1.0);
+        assert!(!info.is_damaged());
+
+        assert!(matches!(
+            parse("Pages: 1\nEncrypted: no\n"),
+            Err(PDF2ImageError::UnableToExtractLinearizationStatus)
+        ));
+        assert!(matches!(
+            parse("Pages: 1\nOptimized: no\n"),
+            Err(PDF2ImageError::UnableToExtractEncryptionStatus)
+        ));
+    }
+
+    #[test]
+    fn parse_pdf_info_damaged() {
+        let info = parse_pdf_info(
+            b"Pages: 1\nEncrypted: no\nOptimized: no\n",
+            b"Syntax Error: PDF file is damaged - attempting to reconstruct xref table...\n",
+            b"%PDF-1.7",
+        )
+        .unwrap();
+
+        assert!(info.is_damaged());
+    }
+
+    #[test]
+    fn contact_sheet_size_fills_partial_rows() {
+        assert_eq!(contact_sheet_size(5, 2, (100, 150)).unwrap(), (200, 450));
+        assert_eq!(contact_sheet_size(4, 2, (100, 150)).unwrap(), (200, 300));
+        assert_eq!(contact_sheet_size(0, 2, (100, 150)).unwrap(), (200, 0));
+    }
+
+    #[test]
+    fn contact_sheet_size_rejects_empty_and_oversized_cells() {
+        for (columns, cell) in [
+            (2, (0, 150)),
+            (2, (100, 0)),
+            (u32::MAX, (2, 1)),
+            (1, (u32::MAX, u32::MAX)),
+        ] {
+            assert!(matches!(
+                contact_sheet_size(5, columns, cell),
+                Err(PDF2ImageError::InvalidOptions { field: "cell", .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn scan_raw_names_found() {
+        let pdf = b"<< /Type /Catalog /AcroForm 5 0 R /Names << /JavaScript 6 0 R >> >>\n\
+            << /Type /Page /UserUnit 2.5 >>";
+
+        assert_eq!(
+            scan_raw_names(pdf),
+            RawNames {
+                javascript: true,
+                acroform: true,
+                user_unit: Some(2.5),
+            }
+        );
+    }
+
+    #[test]
+    fn scan_raw_names_requires_delimiters() {
+        let pdf = b"<< /JSON 1 /AcroFormX 2 /UserUnits 3 >>";
+
+        assert_eq!(scan_raw_names(pdf), RawNames::default());
+    }
+
+    #[test]
+    fn scan_raw_names_short_js_name() {
+        assert!(scan_raw_names(b"<< /S /JavaScript >>").javascript);
+        assert!(scan_raw_names(b"<< /S/JS(app.alert(1))>>").javascript);
+        assert!(scan_raw_names(b"/JS").javascript);
+        assert!(!scan_raw_names(b"<< /Type /Page >>").javascript);
+    }
+
+    #[test]
+    fn encode_webp_from_png() {
+        let image = image::RgbImage::from_pixel(4, 4, image::Rgb([200, 30, 30]));
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgb8(image)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let webp = encode_webp(&png).unwrap();
+
+        assert_eq!(&webp[..4], b"RIFF");
+        assert_eq!(&webp[8..12], b"WEBP");
+        let decoded = image::load_from_memory_with_format(&webp, image::ImageFormat::WebP).unwrap();
+        assert_eq!(decoded.dimensions(), (4, 4));
+    }
+
+    #[test]
+    fn fit_exact_pads_and_crops_centered() {
+        let background = image::Rgba([255, 255, 255, 255]);
+        // One pixel short in width and one pixel over in height
+        let page = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            99,
+            101,
+            image::Rgb([0, 0, 0]),
+        ));
+
+        let fitted = fit_exact(&page, 100, 100, background);
+
+        assert_eq!(fitted.dimensions(), (100, 100));
+        assert_eq!(fitted.color(), image::ColorType::Rgb8);
+        let fitted = fitted.to_rgb8();
+        // Odd differences are padded or cropped at the right and bottom
+        assert_eq!(fitted.get_pixel(99, 50).0, [255, 255, 255]);
+        assert_eq!(fitted.get_pixel(0, 0).0, [0, 0, 0]);
+        assert_eq!(fitted.get_pixel(98, 99).0, [0, 0, 0]);
+    }
+
+    #[test]
+    fn postprocess_applies_exact_size_after_resize() {
+        let options = RenderOptions {
+            resize: Some((50, 40, image::imageops::FilterType::Nearest)),
+            exact_size: Some((60, 60)),
+            ..RenderOptions::default()
+        };
+        let page = image::DynamicImage::ImageLuma8(image::GrayImage::new(200, 100));
+
+        let processed = postprocess_page(page, &options);
+
+        assert_eq!(processed.dimensions(), (60, 60));
+        assert_eq!(processed.color(), image::ColorType::L8);
+    }
+
+    #[test]
+    fn postprocess_resizes_with_filter() {
+        // A repeating horizontal pattern so the filters sample different values
+        let page = image::DynamicImage::ImageLuma8(image::GrayImage::from_fn(200, 100, |x, _| {
+            image::Luma([(x % 7 * 36) as u8])
+        }));
+        let resized = |filter| {
+            let options = RenderOptions {
+                resize: Some((50, 40, filter)),
+                ..RenderOptions::default()
+            };
+            postprocess_page(page.clone(), &options)
+        };
+
+        let lanczos = resized(image::imageops::FilterType::Lanczos3);
+        let nearest = resized(image::imageops::FilterType::Nearest);
+
+        assert_eq!(lanczos.dimensions(), (50, 40));
+        assert_eq!(nearest.dimensions(), (50, 40));
+        assert_ne!(lanczos.as_bytes(), nearest.as_bytes());
+    }
+
+    #[test]
+    fn normalize_text_strips_bom_and_line_endings() {
+        assert_eq!(
+            normalize_text("\u{feff}first\r\nsecond\rthird\n\x0C"),
+            "first\nsecond\nthird\n\x0C"
+        );
+        // Only a leading byte order mark is removed
+        assert_eq!(normalize_text("a\u{feff}b"), "a\u{feff}b");
+    }
+
+    #[test]
+    fn decode_text_normalizes_when_enabled() {
+        let output = b"\xEF\xBB\xBFline one\r\nline two\r\n".to_vec();
+        let normalized = RenderOptions {
+            normalize_text: true,
+            ..RenderOptions::default()
+        };
+
+        assert_eq!(
+            decode_text(output.clone(), &normalized).unwrap(),
+            "line one\nline two\n"
+        );
+        assert_eq!(
+            decode_text(output, &RenderOptions::default()).unwrap(),
+            "\u{feff}line one\r\nline two\r\n"
+        );
+    }
+
+    /// Options rendering with pdftocairo where neither renderer is installed
+    fn options_without_renderers(name: &str) -> RenderOptions {
+        RenderOptions {
+            pdftocairo: true,
+            fallback_to_pdftoppm: true,
+            binary_overrides: HashMap::from([
+                ("pdftocairo", format!("/nonexistent/{name}/pdftocairo")),
+                ("pdftoppm", format!("/nonexistent/{name}/pdftoppm")),
+            ]),
+            ..RenderOptions::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback_fails_without_any_renderer() {
+        let options = options_without_renderers("fallback_fails");
+
+        let result = render_pdf_single_page(b"", &PdfInfo::with_page_count(1), 1, &options).await;
+
+        assert!(matches!(result, Err(PDF2ImageError::NoRendererAvailable)));
+    }
+
+    #[tokio::test]
+    async fn fallback_reports_warning() {
+        let warnings = crate::render_options::WarningsSink::default();
+        let options = RenderOptions {
+            warnings_sink: Some(warnings.clone()),
+            ..options_without_renderers("fallback_warns")
+        };
+
+        let _ = render_pdf_single_page(b"", &PdfInfo::with_page_count(1), 1, &options).await;
+
+        assert_eq!(
+            *warnings.lock().unwrap(),
+            ["pdftocairo is not available, falling back to pdftoppm"]
+        );
+    }
+
+    #[tokio::test]
+    async fn render_rejects_pages_over_max_pixels() {
+        // A US letter page at 300 DPI is 2550x3300 pixels
+        let options = RenderOptions {
+            resolution: DPI::Uniform(300.0),
+            max_pixels: Some(1_000_000),
+            binary_overrides: HashMap::from([(
+                "pdftoppm",
+                "/nonexistent/max_pixels/pdftoppm".to_string(),
+            )]),
+            ..RenderOptions::default()
+        };
+
+        let result = render_pdf_single_page(b"", &PdfInfo::with_page_count(1), 1, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(PDF2ImageError::OutputTooLarge {
+                pixels: 8_415_000,
+                limit: 1_000_000
+            })
+        ));
+    }
+
+    #[test]
+    fn effective_options_downscales_to_budget() {
+        let info = PdfInfo::with_page_count(1);
+        let options = RenderOptions {
+            resolution: DPI::Uniform(10_000.0),
+            auto_downscale_to: Some(4_000_000),
+            ..RenderOptions::default()
+        };
+
+        let adjusted = effective_options(&info, 1, &options).unwrap();
+
+        let pixels = adjusted.estimated_pixels(info.page_size(1).unwrap());
+        assert!(pixels <= 4_000_000);
+        // The resolution is only lowered as far as needed
+        assert!(pixels > 3_500_000);
+    }
+
+    #[test]
+    fn effective_options_ignores_budget_when_scaling_or_cropping() {
+        let info = PdfInfo::with_page_count(1);
+        let budget = RenderOptions {
+            resolution: DPI::Uniform(10_000.0),
+            auto_downscale_to: Some(4_000_000),
+            ..RenderOptions::default()
+        };
+
+        let scaled = RenderOptions {
+            scale: Some(Scale::Uniform(5000)),
+            ..budget.clone()
+        };
+        let cropped = RenderOptions {
+            crop: Some(crate::render_options::Crop::new(0, 0, 5000, 5000)),
+            ..budget
+        };
+
+        assert!(effective_options(&info, 1, &scaled).is_none());
+        assert!(effective_options(&info, 1, &cropped).is_none());
+    }
+
+    #[tokio::test]
+    async fn copy_limited_allows_exact_fit() {
+        let mut output = Vec::new();
+
+        let copied = copy_limited(&mut &b"12345"[..], &mut output, Some(5))
+            .await
+            .unwrap();
+
+        assert_eq!(copied, 5);
+        assert_eq!(output, b"12345");
+    }
+
+    #[tokio::test]
+    async fn copy_limited_rejects_one_byte_over() {
+        let mut output = Vec::new();
+
+        let result = copy_limited(&mut &b"123456"[..], &mut output, Some(5)).await;
+
+        assert!(matches!(
+            result,
+            Err(PDF2ImageError::OutputLimitExceeded { limit: 5 })
+        ));
+    }
+
+    #[test]
+    fn decode_text_strict_rejects_invalid_utf8() {
+        let strict = RenderOptions {
+            text_encoding: TextEncoding::Strict,
+            ..RenderOptions::default()
+        };
+
+        assert!(matches!(
+            decode_text(b"caf\xE9".to_vec(), &strict),
+            Err(PDF2ImageError::InvalidUtf8(_))
+        ));
+        assert_eq!(
+            decode_text("café".as_bytes().to_vec(), &strict).unwrap(),
+            "café"
+        );
+    }
+
+    #[tokio::test]
+    async fn multi_page_errors_carry_the_page() {
+        let options = RenderOptions {
+            binary_overrides: HashMap::from([(
+                "pdftoppm",
+                "/nonexistent/multi_page/pdftoppm".to_string(),
+            )]),
+            ..RenderOptions::default()
+        };
+
+        let result = render_pdf_multi_page(
+            b"",
+            &PdfInfo::with_page_count(3),
+            Pages::Specific(vec![2]),
+            &options,
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(PDF2ImageError::PageRender { page: 2, source })
+                if matches!(*source, PDF2ImageError::Io(_))
+        ));
+    }
 }
@@ -1,17 +1,36 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::error::{PDF2ImageError, Result};
+
+/// Shared collection of the non-fatal warnings poppler writes to stderr
+pub type WarningsSink = Arc<Mutex<Vec<String>>>;
+
 /// Options for rendering PDFs
-#[derive(derive_builder::Builder)]
+#[derive(Clone, derive_builder::Builder)]
+#[builder(build_fn(validate = "Self::validate", error = "PDF2ImageError"))]
 pub struct RenderOptions {
-    #[builder(default = "DPI::Uniform(150)")]
+    #[builder(default = "DPI::Uniform(150.0)")]
     /// Resolution in dots per inch
     pub resolution: DPI,
     #[builder(setter(into, strip_option), default)]
     /// Scale pages to a certain number of pixels
     pub scale: Option<Scale>,
+    #[builder(setter(into, strip_option), default)]
+    /// Scale pages to an exact pixel width and/or height
+    ///
+    /// Mutually exclusive with [RenderOptions::scale], when set
+    /// poppler ignores the [RenderOptions::resolution]
+    pub scale_to: Option<ScaleTo>,
     #[builder(default)]
     /// Render pages in grayscale
     pub greyscale: bool,
     #[builder(setter(into, strip_option), default)]
     /// Crop a specific section of the page
+    ///
+    /// The crop region is relative to the page CropBox unless
+    /// [RenderOptions::crop_from_media_box] is set
     pub crop: Option<Crop>,
     #[builder(setter(into, strip_option), default)]
     /// Password to unlock encrypted PDFs
@@ -19,39 +38,452 @@ pub struct RenderOptions {
     /// Use pdftocairo instead of pdftoppm
     #[builder(default)]
     pub pdftocairo: bool,
+    /// Render with pdftoppm when pdftocairo is enabled but not installed,
+    /// a warning is added to the [RenderOptions::warnings_sink] when this happens
+    #[builder(default)]
+    pub fallback_to_pdftoppm: bool,
+    #[builder(setter(into, strip_option), default)]
+    /// Collects each line poppler writes to stderr, including
+    /// warnings from renders that otherwise succeeded
+    pub warnings_sink: Option<WarningsSink>,
+    #[builder(default)]
+    /// How the extracted text output is decoded
+    pub text_encoding: TextEncoding,
+    #[builder(default)]
+    /// Produce byte-stable PNG output for identical inputs, useful
+    /// when caching rendered pages by hash
+    ///
+    /// Only PNG output is normalized, an explicit JPEG [RenderOptions::format]
+    /// is rendered as poppler produces it
+    pub deterministic: bool,
+    #[builder(default)]
+    /// Take the [RenderOptions::crop] region relative to the MediaBox
+    /// instead of the CropBox
+    ///
+    /// Both backends render the whole MediaBox when there is no crop so
+    /// this has no effect without one
+    pub crop_from_media_box: bool,
+    #[builder(setter(into, strip_option), default)]
+    /// Directory used for any intermediate files, defaults to
+    /// the system temp directory
+    pub temp_dir: Option<PathBuf>,
+    #[builder(setter(into, strip_option), default)]
+    /// Resize the decoded page to an exact width and height using the filter
+    ///
+    /// Unlike [RenderOptions::scale] this is applied after poppler renders
+    /// the page, allowing a high resolution render to be downsampled
+    pub resize: Option<(u32, u32, image::imageops::FilterType)>,
+    #[builder(default)]
+    /// Executables to use in place of the default poppler binaries, keyed
+    /// by the default binary name (e.g. "pdftoppm" -> "my-pdftoppm-wrapper")
+    pub binary_overrides: HashMap<&'static str, String>,
+    #[builder(setter(into, strip_option), default)]
+    /// Composite pages with an alpha channel over this color,
+    /// producing an RGB image without transparency
+    pub flatten_background: Option<image::Rgba<u8>>,
+    #[builder(setter(into, strip_option), default)]
+    /// Maximum number of pixels a rendered page may contain, pages that
+    /// would exceed this are rejected before poppler is spawned
+    pub max_pixels: Option<u64>,
+    #[builder(default = "true")]
+    /// Render annotations and form fields, when disabled pdftoppm is
+    /// passed -hide-annotations (not supported by pdftocairo)
+    pub render_annotations: bool,
+    #[builder(setter(into), default)]
+    /// Environment variables set on every spawned poppler process (e.g.
+    /// `FONTCONFIG_PATH`), these supplement the inherited environment
+    pub env: Vec<(String, String)>,
+    #[builder(default)]
+    /// Omit pages without any non-whitespace text from the
+    /// [crate::pdftext_multi_page] output
+    pub skip_empty_pages: bool,
+    #[builder(setter(into, strip_option), default)]
+    /// Line ending style of extracted text, defaults to the platform
+    /// line ending (only affects text extraction, not rendering)
+    pub eol: Option<EolMode>,
+    #[builder(default)]
+    /// Don't insert form feeds between pages of extracted text (only
+    /// affects text extraction, not rendering)
+    ///
+    /// Text from a contiguous page range can then no longer be split
+    /// into pages, so each page is extracted with its own pdftotext process
+    pub no_page_breaks: bool,
+    #[builder(default)]
+    /// Strip a leading byte order mark and convert all line endings of
+    /// extracted text to `\n`, regardless of [RenderOptions::eol]
+    pub normalize_text: bool,
+    #[builder(setter(into, strip_option), default)]
+    /// The format poppler renders pages as, see [RenderOptions::output_format]
+    /// for the default
+    pub format: Option<OutputFormat>,
+    #[builder(default)]
+    /// Render pages as 1-bit monochrome images
+    pub mono: bool,
+    #[builder(setter(into, strip_option), default)]
+    /// Maximum number of bytes read from a poppler command's output, the
+    /// command is killed once it writes more than this (not applied to
+    /// [crate::pdftext_stream] which never buffers the whole output)
+    pub max_output_bytes: Option<u64>,
+    #[builder(default)]
+    /// Multiply the [RenderOptions::resolution] by the [crate::PdfInfo::user_unit]
+    /// so pages with a /UserUnit render at their physical size, PDFs
+    /// without a /UserUnit use a unit of 1.0 and are unaffected
+    pub respect_user_unit: bool,
+    #[builder(default)]
+    /// Fail multi-page operations with [PDF2ImageError::PageOutOfRange]
+    /// when a requested page doesn't exist instead of skipping it
+    pub strict_pages: bool,
+    #[builder(setter(into, strip_option), default)]
+    /// Lower the resolution of pages that would render to more than this
+    /// many pixels so they fit, rather than failing like [RenderOptions::max_pixels]
+    ///
+    /// Only applies when the resolution determines the output size (no
+    /// scale, scale_to or crop) and the page size is known, use
+    /// [crate::render_pdf_single_page_with_dpi] to get the resolution used
+    pub auto_downscale_to: Option<u64>,
+    #[builder(setter(into, strip_option), default)]
+    /// Pad or crop the decoded page to exactly this width and height,
+    /// keeping the content centered, so the output size doesn't depend on
+    /// how the poppler version rounds the page dimensions
+    ///
+    /// Padding uses the [RenderOptions::flatten_background] color, white
+    /// when it isn't set. Applied after [RenderOptions::resize]
+    pub exact_size: Option<(u32, u32)>,
 }
 
 impl Default for RenderOptions {
     fn default() -> Self {
         Self {
-            resolution: DPI::Uniform(150),
+            resolution: DPI::Uniform(150.0),
             scale: None,
+            scale_to: None,
             greyscale: false,
             crop: None,
             password: None,
             pdftocairo: false,
+            fallback_to_pdftoppm: false,
+            warnings_sink: None,
+            text_encoding: TextEncoding::default(),
+            deterministic: false,
+            crop_from_media_box: false,
+            temp_dir: None,
+            resize: None,
+            binary_overrides: HashMap::new(),
+            flatten_background: None,
+            max_pixels: None,
+            render_annotations: true,
+            env: Vec::new(),
+            skip_empty_pages: false,
+            eol: None,
+            no_page_breaks: false,
+            normalize_text: false,
+            format: None,
+            mono: false,
+            max_output_bytes: None,
+            respect_user_unit: false,
+            strict_pages: false,
+            auto_downscale_to: None,
+            exact_size: None,
+        }
+    }
+}
+
+impl RenderOptionsBuilder {
+    /// Rejects option combinations poppler would silently misinterpret
+    ///
+    /// - `resolution` must be positive and finite
+    /// - at most one of an explicit `resolution`, `scale` and `scale_to`
+    /// - `scale_to` needs at least one axis
+    /// - `crop` needs a non-zero width and height
+    fn validate(&self) -> Result<()> {
+        let invalid = |field: &'static str, reason: &str| PDF2ImageError::InvalidOptions {
+            field,
+            reason: reason.to_string(),
+        };
+
+        if let Some(resolution) = &self.resolution {
+            let valid = |dpi: f32| dpi.is_finite() && dpi > 0.0;
+            let is_valid = match *resolution {
+                DPI::Uniform(dpi) => valid(dpi),
+                DPI::XY(dpi_x, dpi_y) => valid(dpi_x) && valid(dpi_y),
+            };
+
+            if !is_valid {
+                return Err(invalid("resolution", "must be positive and finite"));
+            }
+        }
+
+        let scale_set = matches!(self.scale, Some(Some(_)));
+
+        if let Some(Some(scale_to)) = &self.scale_to {
+            if scale_to.x.is_none() && scale_to.y.is_none() {
+                return Err(invalid("scale_to", "requires at least one axis to be set"));
+            }
+
+            if scale_set {
+                return Err(invalid("scale_to", "cannot be used together with scale"));
+            }
+
+            if self.resolution.is_some() {
+                return Err(invalid(
+                    "scale_to",
+                    "cannot be used together with resolution",
+                ));
+            }
+        }
+
+        if scale_set && self.resolution.is_some() {
+            return Err(invalid("scale", "cannot be used together with resolution"));
+        }
+
+        if let Some(Some(crop)) = &self.crop {
+            if crop.inner.width == 0 || crop.inner.height == 0 {
+                return Err(invalid("crop", "must have a non-zero width and height"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<derive_builder::UninitializedFieldError> for PDF2ImageError {
+    fn from(err: derive_builder::UninitializedFieldError) -> Self {
+        PDF2ImageError::InvalidOptions {
+            field: err.field_name(),
+            reason: "must be set".to_string(),
         }
     }
 }
 
 impl RenderOptions {
-    pub fn to_cli_args(&self) -> Vec<String> {
+    /// Creates a copy of these options with any fields set in the
+    /// `overrides` replacing the current values
+    pub fn merge(&self, overrides: RenderOptionsPatch) -> RenderOptions {
+        let mut options = self.clone();
+
+        if let Some(resolution) = overrides.resolution {
+            options.resolution = resolution;
+        }
+        if let Some(scale) = overrides.scale {
+            options.scale = Some(scale);
+        }
+        if let Some(scale_to) = overrides.scale_to {
+            options.scale_to = Some(scale_to);
+        }
+        if let Some(greyscale) = overrides.greyscale {
+            options.greyscale = greyscale;
+        }
+        if let Some(crop) = overrides.crop {
+            options.crop = Some(crop);
+        }
+        if let Some(password) = overrides.password {
+            options.password = Some(password);
+        }
+        if let Some(pdftocairo) = overrides.pdftocairo {
+            options.pdftocairo = pdftocairo;
+        }
+        if let Some(fallback_to_pdftoppm) = overrides.fallback_to_pdftoppm {
+            options.fallback_to_pdftoppm = fallback_to_pdftoppm;
+        }
+        if let Some(warnings_sink) = overrides.warnings_sink {
+            options.warnings_sink = Some(warnings_sink);
+        }
+        if let Some(text_encoding) = overrides.text_encoding {
+            options.text_encoding = text_encoding;
+        }
+        if let Some(deterministic) = overrides.deterministic {
+            options.deterministic = deterministic;
+        }
+        if let Some(crop_from_media_box) = overrides.crop_from_media_box {
+            options.crop_from_media_box = crop_from_media_box;
+        }
+        if let Some(temp_dir) = overrides.temp_dir {
+            options.temp_dir = Some(temp_dir);
+        }
+        if let Some(resize) = overrides.resize {
+            options.resize = Some(resize);
+        }
+        if let Some(binary_overrides) = overrides.binary_overrides {
+            options.binary_overrides = binary_overrides;
+        }
+        if let Some(flatten_background) = overrides.flatten_background {
+            options.flatten_background = Some(flatten_background);
+        }
+        if let Some(max_pixels) = overrides.max_pixels {
+            options.max_pixels = Some(max_pixels);
+        }
+        if let Some(render_annotations) = overrides.render_annotations {
+            options.render_annotations = render_annotations;
+        }
+        if let Some(env) = overrides.env {
+            options.env = env;
+        }
+        if let Some(skip_empty_pages) = overrides.skip_empty_pages {
+            options.skip_empty_pages = skip_empty_pages;
+        }
+        if let Some(eol) = overrides.eol {
+            options.eol = Some(eol);
+        }
+        if let Some(no_page_breaks) = overrides.no_page_breaks {
+            options.no_page_breaks = no_page_breaks;
+        }
+        if let Some(normalize_text) = overrides.normalize_text {
+            options.normalize_text = normalize_text;
+        }
+        if let Some(format) = overrides.format {
+            options.format = Some(format);
+        }
+        if let Some(mono) = overrides.mono {
+            options.mono = mono;
+        }
+        if let Some(max_output_bytes) = overrides.max_output_bytes {
+            options.max_output_bytes = Some(max_output_bytes);
+        }
+        if let Some(respect_user_unit) = overrides.respect_user_unit {
+            options.respect_user_unit = respect_user_unit;
+        }
+        if let Some(strict_pages) = overrides.strict_pages {
+            options.strict_pages = strict_pages;
+        }
+        if let Some(auto_downscale_to) = overrides.auto_downscale_to {
+            options.auto_downscale_to = Some(auto_downscale_to);
+        }
+        if let Some(exact_size) = overrides.exact_size {
+            options.exact_size = Some(exact_size);
+        }
+
+        options
+    }
+
+    /// The image format poppler renders pages as
+    ///
+    /// Defaults to PNG when [RenderOptions::deterministic] is set, PNM
+    /// (PBM) for [RenderOptions::mono] with pdftoppm, PNG for mono with
+    /// pdftocairo (which can't write PNM) and JPEG otherwise
+    pub fn output_format(&self) -> image::ImageFormat {
+        match self.format {
+            Some(OutputFormat::Jpeg) => image::ImageFormat::Jpeg,
+            Some(OutputFormat::Png) => image::ImageFormat::Png,
+            Some(OutputFormat::Pnm) => image::ImageFormat::Pnm,
+            Some(OutputFormat::WebP) => image::ImageFormat::WebP,
+            None if self.deterministic => image::ImageFormat::Png,
+            None if self.mono && self.pdftocairo => image::ImageFormat::Png,
+            None if self.mono => image::ImageFormat::Pnm,
+            None => image::ImageFormat::Jpeg,
+        }
+    }
+
+    /// The image format poppler is asked to write, differs from
+    /// [RenderOptions::output_format] for formats that are re-encoded
+    pub(crate) fn poppler_format(&self) -> image::ImageFormat {
+        match self.output_format() {
+            image::ImageFormat::WebP => image::ImageFormat::Png,
+            format => format,
+        }
+    }
+
+    /// The file extension matching the rendered page bytes
+    pub fn file_extension(&self) -> &'static str {
+        match self.output_format() {
+            // pdftoppm picks the netpbm variant from the color options
+            image::ImageFormat::Pnm if self.mono => "pbm",
+            image::ImageFormat::Pnm if self.greyscale => "pgm",
+            image::ImageFormat::Pnm => "ppm",
+            format => format.extensions_str()[0],
+        }
+    }
+
+    /// Estimates the number of pixels poppler will render for a page
+    /// of the provided (width, height) size in points
+    pub fn estimated_pixels(&self, (width, height): (f64, f64)) -> u64 {
+        if let Some(crop) = self
+            .crop
+            .as_ref()
+            .filter(|crop| crop.inner.width > 0 && crop.inner.height > 0)
+        {
+            return u64::from(crop.inner.width) * u64::from(crop.inner.height);
+        }
+
+        let (pixels_x, pixels_y) = if let Some(scale_to) = &self.scale_to {
+            match (scale_to.x, scale_to.y) {
+                (Some(x), Some(y)) => (f64::from(x), f64::from(y)),
+                (Some(x), None) => (f64::from(x), f64::from(x) * height / width),
+                (None, Some(y)) => (f64::from(y) * width / height, f64::from(y)),
+                (None, None) => (width, height),
+            }
+        } else if let Some(scale) = &self.scale {
+            match *scale {
+                Scale::Uniform(size) => {
+                    let factor = f64::from(size) / width.max(height);
+                    (width * factor, height * factor)
+                }
+                Scale::X(x) => (f64::from(x), f64::from(x) * height / width),
+                Scale::Y(y) => (f64::from(y) * width / height, f64::from(y)),
+                Scale::XY(x, y) => (f64::from(x), f64::from(y)),
+            }
+        } else {
+            let (dpi_x, dpi_y) = match self.resolution {
+                DPI::Uniform(dpi) => (dpi, dpi),
+                DPI::XY(dpi_x, dpi_y) => (dpi_x, dpi_y),
+            };
+            (
+                width * f64::from(dpi_x) / 72.0,
+                height * f64::from(dpi_y) / 72.0,
+            )
+        };
+
+        (pixels_x.ceil() * pixels_y.ceil()) as u64
+    }
+
+    /// The HTTP `Content-Type` of the rendered page bytes
+    pub fn content_type(&self) -> &'static str {
+        content_type_for(self.output_format())
+    }
+
+    /// The directory intermediate files are created in
+    pub fn temp_dir_path(&self) -> PathBuf {
+        self.temp_dir.clone().unwrap_or_else(std::env::temp_dir)
+    }
+
+    /// The backend used for rendering pages to images
+    pub fn backend(&self) -> Backend {
+        if self.pdftocairo {
+            Backend::Pdftocairo
+        } else {
+            Backend::Pdftoppm
+        }
+    }
+
+    /// Creates the cli arguments for the provided `backend`, options that
+    /// don't apply to the backend (e.g. image options for pdftotext) are
+    /// omitted while options that can't be honored produce an error
+    pub fn to_cli_args(&self, backend: Backend) -> Result<Vec<String>> {
         let mut args = vec![];
+        let renders_images = backend != Backend::Pdftotext;
 
         match self.resolution {
             DPI::Uniform(dpi) => {
                 args.push("-r".to_string());
                 args.push(dpi.to_string());
             }
-            DPI::XY(dpi_x, dpi_y) => {
+            DPI::XY(dpi_x, dpi_y) if renders_images => {
                 args.push("-rx".to_string());
                 args.push(dpi_x.to_string());
                 args.push("-ry".to_string());
                 args.push(dpi_y.to_string());
             }
+            // pdftotext only uses the resolution for the crop coordinates
+            // and can't accept a separate resolution for each axis
+            DPI::XY(..) if self.crop.is_some() => {
+                return Err(PDF2ImageError::UnsupportedOption {
+                    backend,
+                    option: "DPI::XY with crop",
+                })
+            }
+            DPI::XY(..) => {}
         }
 
-        if let Some(scale) = &self.scale {
+        if let Some(scale) = self.scale.as_ref().filter(|_| renders_images) {
             match scale {
                 Scale::Uniform(scale) => {
                     args.push("-scale-to".to_string());
@@ -74,12 +506,40 @@ impl RenderOptions {
             }
         }
 
-        if self.greyscale {
+        if let Some(scale_to) = self.scale_to.as_ref().filter(|_| renders_images) {
+            args.push("-scale-to-x".to_string());
+            args.push(scale_to.x.map_or(-1, i64::from).to_string());
+            args.push("-scale-to-y".to_string());
+            args.push(scale_to.y.map_or(-1, i64::from).to_string());
+        }
+
+        if self.greyscale && renders_images {
             args.push("-gray".to_string());
         }
 
+        if self.mono && renders_images {
+            args.push("-mono".to_string());
+        }
+
+        if !self.render_annotations {
+            match backend {
+                Backend::Pdftoppm => args.push("-hide-annotations".to_string()),
+                Backend::Pdftocairo => {
+                    return Err(PDF2ImageError::UnsupportedOption {
+                        backend,
+                        option: "render_annotations",
+                    })
+                }
+                // pdftotext extracts text regardless of annotations
+                Backend::Pdftotext => {}
+            }
+        }
+
         if let Some(crop) = &self.crop {
-            args.push("-cropbox".to_string());
+            // pdftotext has no -cropbox option
+            if !self.crop_from_media_box && renders_images {
+                args.push("-cropbox".to_string());
+            }
             let (x, y) = (crop.inner.x, crop.inner.y);
             let (width, height) = (crop.inner.width, crop.inner.height);
             args.push("-x".to_string());
@@ -92,20 +552,109 @@ impl RenderOptions {
             args.push(height.to_string());
         }
 
+        if let Some(eol) = self.eol.filter(|_| !renders_images) {
+            args.push("-eol".to_string());
+            args.push(eol.as_str().to_string());
+        }
+
+        if self.no_page_breaks && !renders_images {
+            args.push("-nopgbrk".to_string());
+        }
+
         if let Some(password) = &self.password {
-            match password {
-                Password::User(password) => {
-                    args.push("-upw".to_string());
-                    args.push(password.clone());
-                }
-                Password::Owner(password) => {
-                    args.push("-opw".to_string());
-                    args.push(password.clone());
-                }
-            }
+            args.extend(password.cli_args());
         }
 
-        args
+        Ok(args)
+    }
+}
+
+/// Overrides applied on top of existing [RenderOptions] by [RenderOptions::merge],
+/// only fields that are `Some` replace the existing values
+#[derive(Default, Clone)]
+pub struct RenderOptionsPatch {
+    pub resolution: Option<DPI>,
+    pub scale: Option<Scale>,
+    pub scale_to: Option<ScaleTo>,
+    pub greyscale: Option<bool>,
+    pub crop: Option<Crop>,
+    pub password: Option<Password>,
+    pub pdftocairo: Option<bool>,
+    pub fallback_to_pdftoppm: Option<bool>,
+    pub warnings_sink: Option<WarningsSink>,
+    pub text_encoding: Option<TextEncoding>,
+    pub deterministic: Option<bool>,
+    pub crop_from_media_box: Option<bool>,
+    pub temp_dir: Option<PathBuf>,
+    pub resize: Option<(u32, u32, image::imageops::FilterType)>,
+    pub binary_overrides: Option<HashMap<&'static str, String>>,
+    pub flatten_background: Option<image::Rgba<u8>>,
+    pub max_pixels: Option<u64>,
+    pub render_annotations: Option<bool>,
+    pub env: Option<Vec<(String, String)>>,
+    pub skip_empty_pages: Option<bool>,
+    pub eol: Option<EolMode>,
+    pub no_page_breaks: Option<bool>,
+    pub normalize_text: Option<bool>,
+    pub format: Option<OutputFormat>,
+    pub mono: Option<bool>,
+    pub max_output_bytes: Option<u64>,
+    pub respect_user_unit: Option<bool>,
+    pub strict_pages: Option<bool>,
+    pub auto_downscale_to: Option<u64>,
+    pub exact_size: Option<(u32, u32)>,
+}
+
+/// The MIME type for the provided image format, suitable for
+/// use as an HTTP `Content-Type` header
+pub fn content_type_for(format: image::ImageFormat) -> &'static str {
+    match format {
+        image::ImageFormat::Png => "image/png",
+        image::ImageFormat::Jpeg => "image/jpeg",
+        image::ImageFormat::Tiff => "image/tiff",
+        image::ImageFormat::WebP => "image/webp",
+        image::ImageFormat::Pnm => "image/x-portable-anymap",
+        format => format.to_mime_type(),
+    }
+}
+
+/// The image formats pages can be rendered as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Jpeg,
+    Png,
+    /// Netpbm output, PBM with [RenderOptions::mono], PGM with
+    /// [RenderOptions::greyscale] and PPM otherwise (pdftoppm only)
+    Pnm,
+    /// Lossless WebP, poppler can't write WebP so pages are rendered as PNG
+    /// and re-encoded, adding a decode and encode step to each page
+    ///
+    /// The image crate can only encode lossless WebP so there is no quality
+    /// setting. The output is usually smaller than the PNG but larger than a
+    /// JPEG for photographic or scanned pages, use [OutputFormat::Jpeg] when
+    /// the size matters more than exact pixels
+    WebP,
+}
+
+/// The poppler executables cli arguments can be created for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// pdftoppm for rendering images
+    Pdftoppm,
+    /// pdftocairo for rendering images and vector formats
+    Pdftocairo,
+    /// pdftotext for text extraction
+    Pdftotext,
+}
+
+impl Backend {
+    /// The name of the executable for this backend
+    pub fn executable(&self) -> &'static str {
+        match self {
+            Backend::Pdftoppm => "pdftoppm",
+            Backend::Pdftocairo => "pdftocairo",
+            Backend::Pdftotext => "pdftotext",
+        }
     }
 }
 
@@ -116,13 +665,56 @@ pub enum Password {
     Owner(String),
 }
 
+impl Password {
+    /// The poppler flag and value passing the password
+    pub(crate) fn cli_args(&self) -> [String; 2] {
+        match self {
+            Password::User(password) => ["-upw".to_string(), password.clone()],
+            Password::Owner(password) => ["-opw".to_string(), password.clone()],
+        }
+    }
+}
+
+/// The end of line convention used by pdftotext
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EolMode {
+    /// `\n` line endings
+    Unix,
+    /// `\r\n` line endings
+    Dos,
+    /// `\r` line endings
+    Mac,
+}
+
+impl EolMode {
+    /// The value passed to pdftotext -eol
+    fn as_str(&self) -> &'static str {
+        match self {
+            EolMode::Unix => "unix",
+            EolMode::Dos => "dos",
+            EolMode::Mac => "mac",
+        }
+    }
+}
+
+/// How the UTF-8 text output from pdftotext is decoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// Replaces invalid UTF-8 sequences with U+FFFD
+    #[default]
+    Lossy,
+    /// Fails with [PDF2ImageError::InvalidUtf8](crate::PDF2ImageError::InvalidUtf8)
+    /// when the output contains invalid UTF-8
+    Strict,
+}
+
 /// Specifies resolution in terms of dots per inch
 #[derive(Debug, Clone)]
 pub enum DPI {
     /// DPI for both axes
-    Uniform(u32),
+    Uniform(f32),
     /// DPI for x and y axis
-    XY(u32, u32),
+    XY(f32, f32),
 }
 
 /// Scales pages to a certain number of pixels
@@ -138,6 +730,36 @@ pub enum Scale {
     XY(u32, u32),
 }
 
+/// Scales pages to an exact number of pixels, axes left as `None`
+/// are scaled automatically to preserve the aspect ratio
+///
+/// At least one axis must be set
+#[derive(Debug, Clone)]
+pub struct ScaleTo {
+    /// Exact output width in pixels
+    pub x: Option<u32>,
+    /// Exact output height in pixels
+    pub y: Option<u32>,
+}
+
+impl ScaleTo {
+    /// Scale to an exact width with an automatic height
+    pub fn width(width: u32) -> Self {
+        Self {
+            x: Some(width),
+            y: None,
+        }
+    }
+
+    /// Scale to an exact height with an automatic width
+    pub fn height(height: u32) -> Self {
+        Self {
+            x: None,
+            y: Some(height),
+        }
+    }
+}
+
 /// Crop a specific section of the page
 #[derive(Debug, Clone)]
 pub struct Crop {
@@ -188,3 +810,353 @@ impl Crop {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(options: &RenderOptions, backend: Backend) -> Vec<String> {
+        options.to_cli_args(backend).unwrap()
+    }
+
+    #[test]
+    fn scale_to_width_uses_auto_height() {
+        let options = RenderOptionsBuilder::default()
+            .scale_to(ScaleTo::width(1024))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            args(&options, Backend::Pdftoppm),
+            ["-r", "150", "-scale-to-x", "1024", "-scale-to-y", "-1"]
+        );
+    }
+
+    #[test]
+    fn scale_to_height_uses_auto_width() {
+        let options = RenderOptionsBuilder::default()
+            .scale_to(ScaleTo::height(768))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            args(&options, Backend::Pdftocairo),
+            ["-r", "150", "-scale-to-x", "-1", "-scale-to-y", "768"]
+        );
+    }
+
+    #[test]
+    fn backends_share_geometry_args() {
+        let options = RenderOptionsBuilder::default()
+            .resolution(DPI::XY(100.0, 200.0))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            args(&options, Backend::Pdftoppm),
+            args(&options, Backend::Pdftocairo)
+        );
+    }
+
+    #[test]
+    fn crop_is_relative_to_crop_box_by_default() {
+        let options = RenderOptionsBuilder::default()
+            .crop(Crop::new(0, 0, 100, 50))
+            .build()
+            .unwrap();
+
+        for backend in [Backend::Pdftoppm, Backend::Pdftocairo] {
+            assert_eq!(
+                args(&options, backend),
+                ["-r", "150", "-cropbox", "-x", "0", "-y", "0", "-W", "100", "-H", "50"]
+            );
+        }
+    }
+
+    #[test]
+    fn crop_from_media_box_omits_cropbox() {
+        let options = RenderOptionsBuilder::default()
+            .crop(Crop::new(0, 0, 100, 50))
+            .crop_from_media_box(true)
+            .build()
+            .unwrap();
+
+        for backend in [Backend::Pdftoppm, Backend::Pdftocairo] {
+            assert!(!args(&options, backend).contains(&"-cropbox".to_string()));
+        }
+    }
+
+    #[test]
+    fn args_for_each_backend() {
+        let options = RenderOptionsBuilder::default()
+            .greyscale(true)
+            .crop(Crop::new(10, 20, 110, 70))
+            .password(Password::User("secret".to_string()))
+            .build()
+            .unwrap();
+
+        let image_args = [
+            "-r", "150", "-gray", "-cropbox", "-x", "10", "-y", "20", "-W", "100", "-H", "50",
+            "-upw", "secret",
+        ];
+        assert_eq!(args(&options, Backend::Pdftoppm), image_args);
+        assert_eq!(args(&options, Backend::Pdftocairo), image_args);
+
+        // pdftotext has no color or -cropbox options
+        assert_eq!(
+            args(&options, Backend::Pdftotext),
+            ["-r", "150", "-x", "10", "-y", "20", "-W", "100", "-H", "50", "-upw", "secret"]
+        );
+    }
+
+    #[test]
+    fn xy_resolution_per_backend() {
+        let options = RenderOptionsBuilder::default()
+            .resolution(DPI::XY(72.0, 144.0))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            args(&options, Backend::Pdftoppm),
+            ["-rx", "72", "-ry", "144"]
+        );
+        assert!(args(&options, Backend::Pdftotext).is_empty());
+
+        let cropped = RenderOptions {
+            crop: Some(Crop::new(0, 0, 10, 10)),
+            ..options
+        };
+        assert!(matches!(
+            cropped.to_cli_args(Backend::Pdftotext),
+            Err(PDF2ImageError::UnsupportedOption {
+                backend: Backend::Pdftotext,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn content_type_for_each_format() {
+        for (format, content_type) in [
+            (image::ImageFormat::Png, "image/png"),
+            (image::ImageFormat::Jpeg, "image/jpeg"),
+            (image::ImageFormat::Tiff, "image/tiff"),
+            (image::ImageFormat::WebP, "image/webp"),
+            (image::ImageFormat::Pnm, "image/x-portable-anymap"),
+        ] {
+            assert_eq!(content_type_for(format), content_type);
+        }
+    }
+
+    fn invalid_field(result: Result<RenderOptions>) -> &'static str {
+        match result {
+            Err(PDF2ImageError::InvalidOptions { field, .. }) => field,
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(_) => panic!("options were accepted"),
+        }
+    }
+
+    #[test]
+    fn fractional_resolution() {
+        let options = RenderOptionsBuilder::default()
+            .resolution(DPI::Uniform(150.5))
+            .build()
+            .unwrap();
+
+        assert_eq!(args(&options, Backend::Pdftoppm), ["-r", "150.5"]);
+    }
+
+    #[test]
+    fn rejects_zero_and_nan_resolution() {
+        for resolution in [
+            DPI::Uniform(0.0),
+            DPI::Uniform(f32::NAN),
+            DPI::XY(150.0, 0.0),
+            DPI::XY(f32::INFINITY, 150.0),
+        ] {
+            let result = RenderOptionsBuilder::default()
+                .resolution(resolution)
+                .build();
+
+            assert_eq!(invalid_field(result), "resolution");
+        }
+    }
+
+    #[test]
+    fn hide_annotations_flag() {
+        let hidden = RenderOptionsBuilder::default()
+            .render_annotations(false)
+            .build()
+            .unwrap();
+
+        assert!(args(&hidden, Backend::Pdftoppm).contains(&"-hide-annotations".to_string()));
+        assert!(!args(&hidden, Backend::Pdftotext).contains(&"-hide-annotations".to_string()));
+        assert!(!args(&RenderOptions::default(), Backend::Pdftoppm)
+            .contains(&"-hide-annotations".to_string()));
+        assert!(matches!(
+            hidden.to_cli_args(Backend::Pdftocairo),
+            Err(PDF2ImageError::UnsupportedOption {
+                backend: Backend::Pdftocairo,
+                option: "render_annotations",
+            })
+        ));
+    }
+
+    #[test]
+    fn poppler_too_old_message() {
+        let err = PDF2ImageError::PopplerTooOld {
+            option: "render_annotations",
+            required: crate::PopplerVersion::new(0, 60, 0),
+            found: crate::PopplerVersion::new(0, 59, 2),
+        };
+
+        assert_eq!(
+            err.to_string(),
+            "render_annotations requires poppler 0.60.0 or newer, found 0.59.2"
+        );
+    }
+
+    #[test]
+    fn text_layout_args_only_for_pdftotext() {
+        let options = RenderOptionsBuilder::default()
+            .eol(EolMode::Dos)
+            .no_page_breaks(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            args(&options, Backend::Pdftotext),
+            ["-r", "150", "-eol", "dos", "-nopgbrk"]
+        );
+        assert_eq!(args(&options, Backend::Pdftoppm), ["-r", "150"]);
+        assert_eq!(args(&options, Backend::Pdftocairo), ["-r", "150"]);
+    }
+
+    #[test]
+    fn mono_default_format_per_backend() {
+        let pdftoppm = RenderOptionsBuilder::default().mono(true).build().unwrap();
+        let pdftocairo = RenderOptionsBuilder::default()
+            .mono(true)
+            .pdftocairo(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(pdftoppm.output_format(), image::ImageFormat::Pnm);
+        assert_eq!(pdftoppm.file_extension(), "pbm");
+        assert_eq!(pdftocairo.output_format(), image::ImageFormat::Png);
+        assert_eq!(pdftocairo.file_extension(), "png");
+    }
+
+    #[test]
+    fn pnm_extension_follows_color_options() {
+        let greyscale = RenderOptionsBuilder::default()
+            .format(OutputFormat::Pnm)
+            .greyscale(true)
+            .build()
+            .unwrap();
+        let color = RenderOptionsBuilder::default()
+            .format(OutputFormat::Pnm)
+            .build()
+            .unwrap();
+
+        assert_eq!(greyscale.file_extension(), "pgm");
+        assert_eq!(color.file_extension(), "ppm");
+    }
+
+    #[test]
+    fn rejects_scale_with_resolution() {
+        let result = RenderOptionsBuilder::default()
+            .resolution(DPI::Uniform(300.0))
+            .scale(Scale::Uniform(1000))
+            .build();
+
+        assert_eq!(invalid_field(result), "scale");
+    }
+
+    #[test]
+    fn rejects_scale_to_with_resolution() {
+        let result = RenderOptionsBuilder::default()
+            .resolution(DPI::Uniform(300.0))
+            .scale_to(ScaleTo::width(1000))
+            .build();
+
+        assert_eq!(invalid_field(result), "scale_to");
+    }
+
+    #[test]
+    fn rejects_scale_to_with_scale() {
+        let result = RenderOptionsBuilder::default()
+            .scale(Scale::Uniform(1000))
+            .scale_to(ScaleTo::width(1000))
+            .build();
+
+        assert_eq!(invalid_field(result), "scale_to");
+    }
+
+    #[test]
+    fn rejects_empty_scale_to() {
+        let result = RenderOptionsBuilder::default()
+            .scale_to(ScaleTo { x: None, y: None })
+            .build();
+
+        assert_eq!(invalid_field(result), "scale_to");
+    }
+
+    #[test]
+    fn rejects_zero_size_crop() {
+        for crop in [
+            Crop::new(10, 10, 10, 50),
+            Crop::from_top_left(100, 0, (0, 0)),
+        ] {
+            let result = RenderOptionsBuilder::default().crop(crop).build();
+
+            assert_eq!(invalid_field(result), "crop");
+        }
+    }
+
+    #[test]
+    fn accepts_scale_without_resolution() {
+        let options = RenderOptionsBuilder::default()
+            .scale(Scale::XY(800, 600))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            args(&options, Backend::Pdftoppm),
+            ["-r", "150", "-scale-to-x", "800", "-scale-to-y", "600"]
+        );
+    }
+
+    #[test]
+    fn merge_only_replaces_patched_fields() {
+        let base = RenderOptionsBuilder::default()
+            .scale_to(ScaleTo::width(1024))
+            .greyscale(true)
+            .crop(Crop::new(0, 0, 100, 100))
+            .password(Password::User("secret".to_string()))
+            .pdftocairo(true)
+            .max_pixels(1_000_000u64)
+            .exact_size((800, 600))
+            .normalize_text(true)
+            .build()
+            .unwrap();
+
+        let merged = base.merge(RenderOptionsPatch {
+            resolution: Some(DPI::Uniform(300.0)),
+            ..RenderOptionsPatch::default()
+        });
+
+        let expected = RenderOptions {
+            resolution: DPI::Uniform(300.0),
+            ..base
+        };
+        for backend in [Backend::Pdftoppm, Backend::Pdftocairo] {
+            assert_eq!(args(&merged, backend), args(&expected, backend));
+        }
+        assert!(matches!(merged.resolution, DPI::Uniform(dpi) if dpi == 300.0));
+        assert!(merged.pdftocairo);
+        assert_eq!(merged.max_pixels, Some(1_000_000));
+        assert_eq!(merged.exact_size, Some((800, 600)));
+        assert!(merged.normalize_text);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::error::Result;
+use crate::pdf::{
+    get_executable_path, pdftext_all_pages, render_pdf_multi_page, render_pdf_single_page, Pages,
+    PdfInfo, POPPLER_BINARIES,
+};
+use crate::render_options::RenderOptions;
+
+/// Reusable renderer holding a set of [RenderOptions] and the resolved
+/// poppler executable paths, the paths are resolved once on creation so
+/// the environment is not read again for each render
+///
+/// Cheap to clone and safe to share between threads
+#[derive(Clone)]
+pub struct Renderer {
+    options: RenderOptions,
+}
+
+// Fails to compile if a field stops the renderer being shared between threads
+const _: () = {
+    fn assert_shareable<T: Send + Sync + Clone>() {}
+    let _ = assert_shareable::<Renderer>;
+};
+
+impl Renderer {
+    /// Creates a new renderer resolving the executable path for each
+    /// poppler binary that isn't already in [RenderOptions::binary_overrides]
+    pub fn new(mut options: RenderOptions) -> Self {
+        for &binary in POPPLER_BINARIES {
+            let executable = get_executable_path(binary, &options);
+            options.binary_overrides.insert(binary, executable);
+        }
+
+        Self { options }
+    }
+
+    /// The options used by this renderer
+    pub fn options(&self) -> &RenderOptions {
+        &self.options
+    }
+
+    /// Reads the PDF info from the provided pdf file data
+    pub async fn read_info(&self, data: &[u8]) -> Result<PdfInfo> {
+        PdfInfo::read_with_options(data, &self.options).await
+    }
+
+    /// Renders a single page of the PDF to an image
+    pub async fn render_page(
+        &self,
+        data: &[u8],
+        info: &PdfInfo,
+        page: u32,
+    ) -> Result<image::DynamicImage> {
+        render_pdf_single_page(data, info, page, &self.options).await
+    }
+
+    /// Renders multiple pages of the PDF to images
+    pub async fn render_multi_page(
+        &self,
+        data: &[u8],
+        info: &PdfInfo,
+        pages: Pages,
+    ) -> Result<Vec<image::DynamicImage>> {
+        render_pdf_multi_page(data, info, pages, &self.options).await
+    }
+
+    /// Extracts the text contents of the PDF pages as one string
+    pub async fn extract_text(&self, data: &[u8], info: &PdfInfo, pages: Pages) -> Result<String> {
+        pdftext_all_pages(data, info, pages, &self.options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_resolves_every_binary() {
+        let mut options = RenderOptions::default();
+        options
+            .binary_overrides
+            .insert("pdftoppm", "/opt/poppler/pdftoppm".to_string());
+
+        let renderer = Renderer::new(options);
+
+        let overrides = &renderer.options().binary_overrides;
+        assert_eq!(overrides.len(), POPPLER_BINARIES.len());
+        for binary in POPPLER_BINARIES {
+            assert!(overrides.contains_key(binary), "{binary} is not resolved");
+        }
+        // Existing overrides are kept
+        assert_eq!(overrides["pdftoppm"], "/opt/poppler/pdftoppm");
+    }
+}
@@ -0,0 +1,104 @@
+use tokio::process::Command;
+
+use crate::error::{PDF2ImageError, Result};
+use crate::pdf::{get_executable_path, run_poppler_checked, Pages, PdfInfo};
+use crate::render_options::RenderOptions;
+
+/// Splits the PDF into single-page PDFs using pdfseparate, returning the
+/// bytes of each requested page's document in page order
+///
+/// The PDF and the separated pages are written to a temporary directory
+/// within [RenderOptions::temp_dir_path] which is removed afterwards
+pub async fn split_pages(
+    data: &[u8],
+    pages: Pages,
+    options: &RenderOptions,
+) -> Result<Vec<Vec<u8>>> {
+    let info = PdfInfo::read_with_options(data, options).await?;
+    let pages_range = pages.resolve_with_options(&info, options)?;
+
+    let (Some(&first), Some(&last)) = (pages_range.iter().min(), pages_range.iter().max()) else {
+        return Ok(Vec::new());
+    };
+
+    let dir = tempfile::Builder::new()
+        .prefix("pdf2image-")
+        .tempdir_in(options.temp_dir_path())?;
+    let input = dir.path().join("input.pdf");
+    tokio::fs::write(&input, data).await?;
+
+    let mut command = Command::new(get_executable_path("pdfseparate", options));
+    command
+        .args(["-f", &first.to_string(), "-l", &last.to_string()])
+        .arg(&input)
+        // pdfseparate replaces %d with the page number
+        .arg(dir.path().join("page-%d.pdf"));
+
+    run_poppler_checked(command, tokio::io::empty(), options, "pdfseparate").await?;
+
+    let mut separated = Vec::with_capacity(pages_range.len());
+    for page in pages_range {
+        let path = dir.path().join(format!("page-{page}.pdf"));
+        separated.push(tokio::fs::read(path).await?);
+    }
+
+    Ok(separated)
+}
+
+/// Merges the PDFs into a single document using pdfunite, keeping the order
+/// of `parts`
+///
+/// The parts and the merged document are written to a temporary directory
+/// within [RenderOptions::temp_dir_path] which is removed afterwards
+pub async fn merge_pdfs(parts: &[Vec<u8>], options: &RenderOptions) -> Result<Vec<u8>> {
+    if parts.is_empty() {
+        return Err(PDF2ImageError::NoPdfsToMerge);
+    }
+
+    let dir = tempfile::Builder::new()
+        .prefix("pdf2image-")
+        .tempdir_in(options.temp_dir_path())?;
+
+    let mut command = Command::new(get_executable_path("pdfunite", options));
+    for (index, part) in parts.iter().enumerate() {
+        let path = dir.path().join(format!("part-{index}.pdf"));
+        tokio::fs::write(&path, part).await?;
+        command.arg(path);
+    }
+
+    let output = dir.path().join("merged.pdf");
+    command.arg(&output);
+
+    run_poppler_checked(command, tokio::io::empty(), options, "pdfunite").await?;
+
+    Ok(tokio::fs::read(output).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn merge_requires_parts() {
+        assert!(matches!(
+            merge_pdfs(&[], &RenderOptions::default()).await,
+            Err(PDF2ImageError::NoPdfsToMerge)
+        ));
+    }
+
+    #[tokio::test]
+    async fn merge_removes_temporary_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let options = RenderOptions {
+            temp_dir: Some(temp_dir.path().to_path_buf()),
+            binary_overrides: HashMap::from([("pdfunite", "/nonexistent/pdfunite".to_string())]),
+            ..RenderOptions::default()
+        };
+
+        let result = merge_pdfs(&[b"%PDF-1.7".to_vec(), b"%PDF-1.7".to_vec()], &options).await;
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use tokio::process::Command;
+
+use crate::error::{PDF2ImageError, Result};
+use crate::pdf::{get_executable_path, run_poppler_output, POPPLER_BINARIES};
+use crate::render_options::RenderOptions;
+
+/// A poppler release version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PopplerVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl PopplerVersion {
+    /// Creates a version from its components
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl fmt::Display for PopplerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Versions already read, keyed by the executable path
+static VERSIONS: Mutex<BTreeMap<String, PopplerVersion>> = Mutex::new(BTreeMap::new());
+
+/// Reads the version of the pdftoppm executable the `options` resolve to,
+/// the result is cached for each executable path
+pub async fn poppler_version(options: &RenderOptions) -> Result<PopplerVersion> {
+    binary_version("pdftoppm", options).await
+}
+
+/// Checks every poppler executable used by this crate, returning each
+/// binary name with its version or the error from running it
+///
+/// Useful for health checks, a missing binary is reported as an
+/// [std::io::ErrorKind::NotFound] i/o error
+pub async fn probe_binaries(options: &RenderOptions) -> Vec<(String, Result<PopplerVersion>)> {
+    let versions = POPPLER_BINARIES
+        .iter()
+        .map(|binary| binary_version(binary, options));
+
+    POPPLER_BINARIES
+        .iter()
+        .map(|binary| binary.to_string())
+        .zip(futures::future::join_all(versions).await)
+        .collect()
+}
+
+/// Reads the version of the provided poppler binary, cached for each executable path
+async fn binary_version(binary: &str, options: &RenderOptions) -> Result<PopplerVersion> {
+    let executable = get_executable_path(binary, options);
+
+    if let Some(version) = versions().get(&executable) {
+        return Ok(*version);
+    }
+
+    let mut command = Command::new(&executable);
+    command.arg("-v");
+
+    let output = run_poppler_output(command, tokio::io::empty(), options, true).await?;
+
+    // The version is written to stderr as "<binary> version 22.02.0"
+    let version = [&output.stderr, &output.stdout]
+        .into_iter()
+        .find_map(|output| parse_version(&String::from_utf8_lossy(output)))
+        .ok_or(PDF2ImageError::UnableToExtractPopplerVersion)?;
+
+    versions().insert(executable, version);
+
+    Ok(version)
+}
+
+/// Ensures the installed poppler is at least the `required` version
+/// for the option, returning [PDF2ImageError::PopplerTooOld] otherwise
+pub(crate) async fn require_version(
+    options: &RenderOptions,
+    option: &'static str,
+    required: PopplerVersion,
+) -> Result<()> {
+    let found = poppler_version(options).await?;
+
+    if found < required {
+        return Err(PDF2ImageError::PopplerTooOld {
+            option,
+            required,
+            found,
+        });
+    }
+
+    Ok(())
+}
+
+fn versions() -> std::sync::MutexGuard<'static, BTreeMap<String, PopplerVersion>> {
+    // The cache is only ever inserted into so a poisoned map is still valid
+    VERSIONS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn parse_version(output: &str) -> Option<PopplerVersion> {
+    let value = output
+        .split_whitespace()
+        .skip_while(|word| *word != "version")
+        .nth(1)?;
+
+    let mut parts = value.split('.').map(str::parse::<u32>);
+    let major = parts.next()?.ok()?;
+    let minor = parts.next()?.ok()?;
+    let patch = parts.next().and_then(|part| part.ok()).unwrap_or(0);
+
+    Some(PopplerVersion::new(major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version_output() {
+        assert_eq!(
+            parse_version("pdftoppm version 22.02.0\nCopyright 2005-2022 The Poppler Developers"),
+            Some(PopplerVersion::new(22, 2, 0))
+        );
+        assert_eq!(
+            parse_version("pdfinfo version 0.86"),
+            Some(PopplerVersion::new(0, 86, 0))
+        );
+        assert_eq!(parse_version("pdftoppm: unknown option"), None);
+    }
+
+    #[test]
+    fn versions_are_ordered() {
+        assert!(PopplerVersion::new(0, 59, 9) < PopplerVersion::new(0, 60, 0));
+        assert!(PopplerVersion::new(21, 12, 0) < PopplerVersion::new(22, 1, 0));
+    }
+
+    #[tokio::test]
+    async fn probe_reports_missing_binaries() {
+        let options = RenderOptions {
+            binary_overrides: POPPLER_BINARIES
+                .iter()
+                .map(|&binary| (binary, format!("/nonexistent/{binary}")))
+                .collect(),
+            ..RenderOptions::default()
+        };
+
+        let probes = probe_binaries(&options).await;
+
+        assert_eq!(probes.len(), POPPLER_BINARIES.len());
+        for ((binary, result), expected) in probes.iter().zip(POPPLER_BINARIES) {
+            assert_eq!(binary, expected);
+            assert!(matches!(
+                result,
+                Err(PDF2ImageError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound
+            ));
+        }
+    }
+}